@@ -0,0 +1,99 @@
+//! This module contains the helpers computing aggregates (e.g. `COUNT(*)` or `SUM`) over the
+//! table of an entity, as well as the trait mapping the types of numeric fields to the types
+//! their sums resolve to.
+
+use std::str::FromStr;
+
+use sqlx::postgres::PgArguments;
+use sqlx::{query_scalar_with, Decode, Executor, Postgres, Result, Type};
+
+use crate::filter::Filter;
+use crate::traits::sql_describe::SqlDescribe;
+
+/// This trait maps the type of a numeric field to the type `SUM` resolves to. Postgres sums up
+/// `BIGINT` as `NUMERIC`, which can't be decoded without a decimal type, so `Column::sum`
+/// retrieves the sum as text and parses it into `Sum` (e.g. an `i128` for an `i64`), which
+/// doesn't overflow. It is implemented for the common numeric types and can be implemented
+/// for custom ones (e.g. a decimal type).
+pub trait Numeric {
+    /// The type of the sum, parsed from its textual representation.
+    type Sum: FromStr;
+
+    /// The SQL type the sum is cast to by `GroupBy::sum`, so the sum of a `BIGINT` column is
+    /// selected as `NUMERIC` and has to be decoded as a decimal type.
+    const SUM_TYPE: &'static str;
+}
+
+/// This macro implements `Numeric` for the given type, summing it up as the given type.
+macro_rules! impl_numeric {
+    ($ty:ty => $sum:ty, $sql_type:literal) => {
+        impl Numeric for $ty {
+            type Sum = $sum;
+
+            const SUM_TYPE: &'static str = $sql_type;
+        }
+    };
+}
+
+impl_numeric!(i16 => i64, "BIGINT");
+impl_numeric!(i32 => i64, "BIGINT");
+impl_numeric!(i64 => i128, "NUMERIC");
+impl_numeric!(f32 => f32, "REAL");
+impl_numeric!(f64 => f64, "DOUBLE PRECISION");
+
+impl<T: Numeric> Numeric for Option<T> {
+    type Sum = T::Sum;
+
+    const SUM_TYPE: &'static str = T::SUM_TYPE;
+}
+
+/// This function selects the given expression (e.g. `COUNT(*)`) from the table of the entity,
+/// restricted to the rows matching the filter (if any).
+pub(crate) async fn fetch_aggregate<'c, E, O, X>(
+    expression: &str,
+    filter: Option<&Filter<E>>,
+    executor: X,
+) -> Result<O>
+where
+    E: SqlDescribe,
+    O: Send + Unpin + for<'r> Decode<'r, Postgres> + Type<Postgres>,
+    X: Executor<'c, Database = Postgres>,
+{
+    let mut arguments = PgArguments::default();
+    let sql = format!(
+        "SELECT {} {};",
+        expression,
+        from_clause(filter, &mut arguments)
+    );
+
+    query_scalar_with(&sql, arguments).fetch_one(executor).await
+}
+
+/// This function checks whether any row of the table of the entity matches the filter (if
+/// any).
+pub(crate) async fn fetch_exists<'c, E, X>(filter: Option<&Filter<E>>, executor: X) -> Result<bool>
+where
+    E: SqlDescribe,
+    X: Executor<'c, Database = Postgres>,
+{
+    let mut arguments = PgArguments::default();
+    let sql = format!(
+        "SELECT EXISTS (SELECT 1 {});",
+        from_clause(filter, &mut arguments)
+    );
+
+    query_scalar_with(&sql, arguments).fetch_one(executor).await
+}
+
+/// This utility function renders the `FROM` clause selecting the table of the entity,
+/// restricted to the rows matching the filter (if any), whose values are bound to the given
+/// arguments.
+fn from_clause<E: SqlDescribe>(filter: Option<&Filter<E>>, arguments: &mut PgArguments) -> String {
+    match filter {
+        Some(filter) => {
+            filter.bind(arguments);
+            format!("FROM {} {}", E::table_name(), filter.where_clause(1))
+        }
+        None => format!("FROM {}", E::table_name()),
+    }
+}
@@ -0,0 +1,134 @@
+//! This module contains the statement catalog: every struct deriving `Describe` registers its
+//! statements (unless annotated with `#[describe(no_catalog)]`), so they can be listed (e.g.
+//! for a review) or prepared up front on every new connection of a pool.
+
+use std::fmt::Write;
+
+use futures_core::future::BoxFuture;
+use sqlx::pool::PoolOptions;
+use sqlx::postgres::PgConnection;
+use sqlx::{Executor, Postgres, Result};
+
+use crate::dialect::Dialect;
+
+/// This struct describes a single entity within the statement catalog. It is registered by
+/// the `Describe` derive macro for every struct which isn't annotated with
+/// `#[describe(no_catalog)]` and should almost never be created directly.
+pub struct CatalogEntry {
+    entity: &'static str,
+    dialect: Dialect,
+    table_name: fn() -> &'static str,
+    statements: fn() -> Vec<(&'static str, &'static str)>,
+}
+
+inventory::collect!(CatalogEntry);
+
+impl CatalogEntry {
+    /// This function creates an entry for the entity with the given (fully qualified) name,
+    /// whose statements are rendered for the given dialect.
+    pub const fn new(
+        entity: &'static str,
+        dialect: Dialect,
+        table_name: fn() -> &'static str,
+        statements: fn() -> Vec<(&'static str, &'static str)>,
+    ) -> Self {
+        Self {
+            entity,
+            dialect,
+            table_name,
+            statements,
+        }
+    }
+
+    /// This method returns the fully qualified name of the entity, e.g. `crate::models::User`.
+    pub fn entity(&self) -> &'static str {
+        self.entity
+    }
+
+    /// This method returns the dialect the statements are rendered for.
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// This method returns the quoted name of the table of the entity.
+    pub fn table_name(&self) -> &'static str {
+        (self.table_name)()
+    }
+
+    /// This method returns all statements of the entity alongside the names of their
+    /// functions. Empty statements (e.g. `update_by_pk` and `delete_by_pk` of an entity
+    /// without a primary key) are skipped.
+    pub fn statements(&self) -> Vec<(&'static str, &'static str)> {
+        (self.statements)()
+            .into_iter()
+            .filter(|(_, statement)| !statement.is_empty())
+            .collect()
+    }
+}
+
+/// This function returns all registered entities, ordered by their names.
+pub fn catalog() -> Vec<&'static CatalogEntry> {
+    let mut entries = inventory::iter::<CatalogEntry>
+        .into_iter()
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.entity);
+    entries
+}
+
+/// This function renders the entire catalog as SQL script, listing the statements of every
+/// entity preceded by comments naming the entity and the function, e.g. for a review.
+pub fn dump_catalog() -> String {
+    let mut dump = String::new();
+    for entry in catalog() {
+        let _ = writeln!(dump, "-- {} ({})", entry.entity(), entry.table_name());
+        for (name, statement) in entry.statements() {
+            let _ = writeln!(dump, "-- {}\n{}", name, statement);
+        }
+        dump.push('\n');
+    }
+    dump
+}
+
+/// This function prepares all statements of the entities rendered for Postgres on the given
+/// connection, so they are contained within its statement cache. Statements which can't be
+/// prepared (e.g. as the table of an entity doesn't exist) are skipped and reported as errors
+/// (with the `tracing` feature); their number is returned. Entities whose tables don't exist
+/// on every connection can be excluded using `#[describe(no_catalog)]`.
+pub async fn prepare_catalog(connection: &mut PgConnection) -> usize {
+    let mut failed = 0;
+    for entry in catalog() {
+        if entry.dialect() != Dialect::Postgres {
+            continue;
+        }
+        for (_name, statement) in entry.statements() {
+            if let Err(_e) = connection.prepare(statement).await {
+                #[cfg(feature = "tracing")]
+                tracing::error!(
+                    entity = entry.entity(),
+                    statement = _name,
+                    error = %_e,
+                    "failed to prepare statement"
+                );
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+/// This function configures the pool to prepare all statements of the catalog (see
+/// `prepare_catalog`) on every new connection, so the first execution of each statement
+/// doesn't have to wait for its preparation. Statements which can't be prepared are skipped,
+/// so the connection is established nonetheless.
+///
+/// The hook replaces any `after_connect` hook set before (and is replaced by one set
+/// afterwards). To combine it with another hook, call `prepare_catalog` within that hook
+/// instead.
+pub fn warm_up(options: PoolOptions<Postgres>) -> PoolOptions<Postgres> {
+    options.after_connect(|connection| -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            prepare_catalog(connection).await;
+            Ok(())
+        })
+    })
+}
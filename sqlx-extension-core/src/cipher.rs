@@ -0,0 +1,231 @@
+//! This module encrypts the fields annotated with `encrypted` at the application level.
+//! The fields are encrypted using the registered `FieldCipher` whenever they are bound and
+//! decrypted whenever they are decoded from a row. Every stored value is prefixed by the id
+//! of the key it was encrypted with, so keys can be rotated: values encrypted with an older
+//! key are still decrypted, while every value written is encrypted with the current key.
+
+use std::convert::TryInto;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::traits::sql_describe::SqlDescribe;
+
+/// The length of the key id prefixed to every stored value (a big-endian `u32`).
+const KEY_ID_LENGTH: usize = 4;
+
+/// The cipher used to encrypt and decrypt all fields.
+static FIELD_CIPHER: Lazy<RwLock<Option<Arc<dyn FieldCipher>>>> = Lazy::new(Default::default);
+
+/// This trait describes a cipher encrypting single fields. Implementations usually hold
+/// multiple keys: the current one, which encrypts all values written, as well as all former
+/// ones, which are still required to decrypt values written before a rotation.
+pub trait FieldCipher: Send + Sync {
+    /// This method returns the id of the key all values are encrypted with.
+    fn current_key_id(&self) -> u32;
+
+    /// This method encrypts the given plaintext using the key with the given id (which is
+    /// always the current one).
+    fn encrypt(&self, key_id: u32, plaintext: &[u8]) -> Vec<u8>;
+
+    /// This method decrypts the given ciphertext using the key with the given id.
+    fn decrypt(&self, key_id: u32, ciphertext: &[u8]) -> Result<Vec<u8>, CipherError>;
+}
+
+/// This error is returned in case a stored value can't be decrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CipherError {
+    /// No cipher was registered using `set_field_cipher`.
+    MissingCipher,
+    /// The stored value is too short to contain the id of its key.
+    MissingKeyId,
+    /// The key the value was encrypted with isn't known (anymore).
+    UnknownKey { key_id: u32 },
+    /// The cipher failed to decrypt the value, e.g. as it was tampered with.
+    Decryption { reason: String },
+    /// The decrypted value isn't valid for the type of the field.
+    InvalidPlaintext { reason: String },
+}
+
+impl Display for CipherError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::MissingCipher => write!(f, "no field cipher has been registered"),
+            CipherError::MissingKeyId => write!(f, "the encrypted value lacks its key id"),
+            CipherError::UnknownKey { key_id } => write!(f, "the key `{}` is unknown", key_id),
+            CipherError::Decryption { reason } => write!(f, "decryption failed: {}", reason),
+            CipherError::InvalidPlaintext { reason } => {
+                write!(f, "the decrypted value is invalid: {}", reason)
+            }
+        }
+    }
+}
+
+impl StdError for CipherError {}
+
+/// This trait converts the value of an encrypted field from and to its plaintext.
+pub trait Encryptable: Sized {
+    /// This method returns the plaintext of the value.
+    fn to_plaintext(&self) -> Vec<u8>;
+
+    /// This function restores the value from its plaintext.
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, CipherError>;
+}
+
+impl Encryptable for String {
+    fn to_plaintext(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, CipherError> {
+        String::from_utf8(plaintext).map_err(|e| CipherError::InvalidPlaintext {
+            reason: e.to_string(),
+        })
+    }
+}
+
+impl Encryptable for Vec<u8> {
+    fn to_plaintext(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_plaintext(plaintext: Vec<u8>) -> Result<Self, CipherError> {
+        Ok(plaintext)
+    }
+}
+
+/// This function registers the cipher used for all encrypted fields, replacing the former
+/// one (if any).
+pub fn set_field_cipher(cipher: impl FieldCipher + 'static) {
+    *FIELD_CIPHER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(cipher));
+}
+
+/// This function returns the registered cipher (if any).
+pub fn field_cipher() -> Option<Arc<dyn FieldCipher>> {
+    FIELD_CIPHER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// This function encrypts the given plaintext using the current key of the cipher,
+/// prefixing the ciphertext by the id of the key.
+pub fn seal(cipher: &dyn FieldCipher, plaintext: &[u8]) -> Vec<u8> {
+    let key_id = cipher.current_key_id();
+    let mut sealed = key_id.to_be_bytes().to_vec();
+    sealed.extend(cipher.encrypt(key_id, plaintext));
+    sealed
+}
+
+/// This function decrypts the given stored value using the key it was encrypted with.
+pub fn open(cipher: &dyn FieldCipher, sealed: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let key_id = key_id_of(sealed).ok_or(CipherError::MissingKeyId)?;
+    cipher.decrypt(key_id, &sealed[KEY_ID_LENGTH..])
+}
+
+/// This function returns the id of the key the given stored value was encrypted with, which
+/// allows finding the values which still have to be rotated.
+pub fn key_id_of(sealed: &[u8]) -> Option<u32> {
+    sealed
+        .get(..KEY_ID_LENGTH)
+        .map(|prefix| u32::from_be_bytes(prefix.try_into().unwrap()))
+}
+
+/// This function fails in case the given entity has encrypted fields, but no cipher has been
+/// registered. It is called by all operations of this crate before binding an entity (or
+/// its changeset), so a missing cipher is returned as an error instead of a panic.
+pub fn ensure_field_cipher<E: SqlDescribe + ?Sized>() -> sqlx::Result<()> {
+    if E::has_encrypted_fields() && field_cipher().is_none() {
+        return Err(sqlx::Error::Configuration(Box::new(
+            CipherError::MissingCipher,
+        )));
+    }
+    Ok(())
+}
+
+/// This function encrypts the given field using the registered cipher. It is called by the
+/// code generated for fields annotated with `encrypted` whenever they are bound.
+///
+/// # Panics
+/// This function panics in case no cipher has been registered, as the field would be
+/// written unencrypted otherwise. When binding the fields manually (e.g. using
+/// `BindFields::bind_all`), call `ensure_field_cipher` beforehand to get an error instead.
+pub fn encrypt_field<T: Encryptable>(value: &T) -> Vec<u8> {
+    let cipher = field_cipher().expect("no field cipher has been registered");
+    seal(&*cipher, &value.to_plaintext())
+}
+
+/// This function decrypts the given column using the registered cipher. It is called by the
+/// code generated for fields annotated with `encrypted` whenever they are decoded.
+pub fn decrypt_column<T: Encryptable>(column: &str, sealed: Vec<u8>) -> sqlx::Result<T> {
+    field_cipher()
+        .ok_or(CipherError::MissingCipher)
+        .and_then(|cipher| open(&*cipher, &sealed))
+        .and_then(T::from_plaintext)
+        .map_err(|e| sqlx::Error::ColumnDecode {
+            index: format!("{:?}", column),
+            source: Box::new(e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A cipher "encrypting" by XORing every byte with its key id, which keeps all former keys.
+    struct XorCipher {
+        current: u32,
+    }
+
+    impl FieldCipher for XorCipher {
+        fn current_key_id(&self) -> u32 {
+            self.current
+        }
+
+        fn encrypt(&self, key_id: u32, plaintext: &[u8]) -> Vec<u8> {
+            plaintext.iter().map(|b| b ^ key_id as u8).collect()
+        }
+
+        fn decrypt(&self, key_id: u32, ciphertext: &[u8]) -> Result<Vec<u8>, CipherError> {
+            if key_id > self.current {
+                return Err(CipherError::UnknownKey { key_id });
+            }
+            Ok(self.encrypt(key_id, ciphertext))
+        }
+    }
+
+    #[test]
+    fn seals_with_the_current_key() {
+        let cipher = XorCipher { current: 7 };
+        let sealed = seal(&cipher, b"secret");
+
+        assert_eq!(key_id_of(&sealed), Some(7));
+        assert_ne!(&sealed[KEY_ID_LENGTH..], b"secret");
+        assert_eq!(open(&cipher, &sealed).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn opens_values_of_former_keys() {
+        let sealed = seal(&XorCipher { current: 1 }, b"secret");
+        let rotated = XorCipher { current: 2 };
+
+        assert_eq!(key_id_of(&sealed), Some(1));
+        assert_eq!(open(&rotated, &sealed).unwrap(), b"secret");
+        assert_eq!(key_id_of(&seal(&rotated, b"secret")), Some(2));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let cipher = XorCipher { current: 1 };
+
+        assert_eq!(key_id_of(&[0, 0, 1]), None);
+        assert_eq!(open(&cipher, &[0, 0, 1]), Err(CipherError::MissingKeyId));
+        assert_eq!(
+            open(&cipher, &seal(&XorCipher { current: 5 }, b"x")),
+            Err(CipherError::UnknownKey { key_id: 5 })
+        );
+        assert!(String::from_plaintext(vec![0xff]).is_err());
+    }
+}
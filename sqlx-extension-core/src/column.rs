@@ -0,0 +1,274 @@
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Formatter};
+use std::str::FromStr;
+
+use futures_core::future::BoxFuture;
+use sqlx::{Decode, Encode, Error, Executor, Postgres, Result, Type};
+
+use crate::aggregate::{fetch_aggregate, Numeric};
+use crate::filter::{is_null, Filter, Operator};
+use crate::instrument::instrument;
+use crate::traits::{
+    postgres_dialect::PostgresDialect, sql_describe::SqlDescribe, tenant_scoped::Unscoped,
+};
+
+/// This struct describes a single column of an entity, combining the name of the column
+/// with an accessor for the corresponding field. The `Describe` derive macro generates an
+/// associated constant for every field (e.g. `User::EMAIL`).
+pub struct Column<E, T> {
+    name: &'static str,
+    label: &'static str,
+    sql_type: Option<&'static str>,
+    accessor: fn(&E) -> &T,
+}
+
+impl<E, T> Column<E, T> {
+    /// This function creates a new column with the given name and field accessor.
+    /// The name is interpolated into statements verbatim, so it has to be quoted if needed.
+    pub const fn new(name: &'static str, accessor: fn(&E) -> &T) -> Self {
+        Self {
+            name,
+            label: name,
+            sql_type: None,
+            accessor,
+        }
+    }
+
+    /// This method sets the label of the column, i.e. the (unquoted) name the column is
+    /// returned as by the database. By default, the label equals the name.
+    pub const fn labeled(self, label: &'static str) -> Self {
+        Self {
+            name: self.name,
+            label,
+            sql_type: self.sql_type,
+            accessor: self.accessor,
+        }
+    }
+
+    /// This method sets the SQL type the values compared to the column are cast to, e.g.
+    /// `my_enum` for a column backed by a Postgres enum (see the `sql_type` attribute).
+    pub const fn typed(self, sql_type: &'static str) -> Self {
+        Self {
+            name: self.name,
+            label: self.label,
+            sql_type: Some(sql_type),
+            accessor: self.accessor,
+        }
+    }
+
+    /// This method returns the name of the column.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// This method returns the label of the column, e.g. to derive the aliases of aggregates.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// This method returns the SQL type the compared values are cast to (if any).
+    pub fn sql_type(&self) -> Option<&'static str> {
+        self.sql_type
+    }
+
+    /// This method returns the value of the column for the given entity.
+    pub fn get<'e>(&self, entity: &'e E) -> &'e T {
+        (self.accessor)(entity)
+    }
+}
+
+impl<E, T> Column<E, T>
+where
+    E: 'static,
+    T: 'static + Send + Sync + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+{
+    /// This method creates a filter which requires the column to be equal to the given value.
+    pub fn eq(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialEq,
+    {
+        self.compare(Operator::Eq, value.into(), |a, b| a == b)
+    }
+
+    /// This method creates a filter which requires the column to differ from the given value.
+    pub fn ne(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialEq,
+    {
+        self.compare(Operator::Ne, value.into(), |a, b| a != b)
+    }
+
+    /// This method creates a filter which requires the column to be less than the given value.
+    pub fn lt(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialOrd,
+    {
+        self.compare(Operator::Lt, value.into(), |a, b| a < b)
+    }
+
+    /// This method creates a filter which requires the column to be less than or equal to the
+    /// given value.
+    pub fn le(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialOrd,
+    {
+        self.compare(Operator::Le, value.into(), |a, b| a <= b)
+    }
+
+    /// This method creates a filter which requires the column to be greater than the given
+    /// value.
+    pub fn gt(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialOrd,
+    {
+        self.compare(Operator::Gt, value.into(), |a, b| a > b)
+    }
+
+    /// This method creates a filter which requires the column to be greater than or equal to
+    /// the given value.
+    pub fn ge(self, value: impl Into<T>) -> Filter<E>
+    where
+        T: PartialOrd,
+    {
+        self.compare(Operator::Ge, value.into(), |a, b| a >= b)
+    }
+
+    /// This utility method creates a comparing filter using the given operator, as well as
+    /// the function used to evaluate it in memory. Just like in SQL, comparing `NULL` to any
+    /// value is unknown.
+    fn compare(self, operator: Operator, value: T, matches: fn(&T, &T) -> bool) -> Filter<E> {
+        let accessor = self.accessor;
+        Filter::compare(self.name, operator, value, move |e, v| {
+            let column = accessor(e);
+            if is_null(column) || is_null(v) {
+                None
+            } else {
+                Some(matches(column, v))
+            }
+        })
+        .cast_to(self.sql_type)
+    }
+}
+
+impl<E: 'static, T: 'static> Column<E, Option<T>> {
+    /// This method creates a filter which requires the column to be `NULL`.
+    pub fn is_null(self) -> Filter<E> {
+        let accessor = self.accessor;
+        Filter::check(self.name, Operator::IsNull, move |e| accessor(e).is_none())
+    }
+
+    /// This method creates a filter which requires the column to be not `NULL`.
+    pub fn is_not_null(self) -> Filter<E> {
+        let accessor = self.accessor;
+        Filter::check(self.name, Operator::IsNotNull, move |e| {
+            accessor(e).is_some()
+        })
+    }
+}
+
+impl<E, T> Column<E, T>
+where
+    E: SqlDescribe + Unscoped + PostgresDialect,
+{
+    /// This method retrieves the minimum of the column across all rows matching the filter
+    /// (if any), which works for every type which can be decoded (e.g. timestamps). In case
+    /// no such row exists (or all values are `NULL`), `None` is returned; the minimum of a
+    /// nullable column is thus never `Some(None)`.
+    pub fn min<'a, 'c, X>(
+        self,
+        filter: Option<&'a Filter<E>>,
+        executor: X,
+    ) -> BoxFuture<'a, Result<Option<T>>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        T: Send + Unpin + for<'r> Decode<'r, Postgres> + Type<Postgres>,
+    {
+        let expression = format!("MIN({})", self.name);
+        instrument::<E, _, _>("min", async move {
+            fetch_aggregate(&expression, filter, executor).await
+        })
+    }
+
+    /// This method retrieves the maximum of the column across all rows matching the filter
+    /// (if any), which works for every type which can be decoded (e.g. timestamps). In case
+    /// no such row exists (or all values are `NULL`), `None` is returned; the maximum of a
+    /// nullable column is thus never `Some(None)`.
+    pub fn max<'a, 'c, X>(
+        self,
+        filter: Option<&'a Filter<E>>,
+        executor: X,
+    ) -> BoxFuture<'a, Result<Option<T>>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        T: Send + Unpin + for<'r> Decode<'r, Postgres> + Type<Postgres>,
+    {
+        let expression = format!("MAX({})", self.name);
+        instrument::<E, _, _>("max", async move {
+            fetch_aggregate(&expression, filter, executor).await
+        })
+    }
+}
+
+impl<E, T> Column<E, T>
+where
+    E: SqlDescribe + Unscoped + PostgresDialect,
+    T: Numeric,
+{
+    /// This method retrieves the sum of the column across all rows matching the filter (if
+    /// any), parsed from its textual representation into `Numeric::Sum`. In case no such row
+    /// exists (or all values are `NULL`), `None` is returned.
+    pub fn sum<'a, 'c, X>(
+        self,
+        filter: Option<&'a Filter<E>>,
+        executor: X,
+    ) -> BoxFuture<'a, Result<Option<T::Sum>>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        <T::Sum as FromStr>::Err: StdError + Send + Sync + 'static,
+    {
+        let expression = format!("CAST(SUM({}) AS TEXT)", self.name);
+        instrument::<E, _, _>("sum", async move {
+            fetch_aggregate::<E, Option<String>, X>(&expression, filter, executor)
+                .await?
+                .map(|sum| sum.parse().map_err(|e| Error::Decode(Box::new(e))))
+                .transpose()
+        })
+    }
+
+    /// This method retrieves the average of the column across all rows matching the filter
+    /// (if any). In case no such row exists (or all values are `NULL`), `None` is returned.
+    pub fn avg<'a, 'c, X>(
+        self,
+        filter: Option<&'a Filter<E>>,
+        executor: X,
+    ) -> BoxFuture<'a, Result<Option<f64>>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+    {
+        let expression = format!("CAST(AVG({}) AS DOUBLE PRECISION)", self.name);
+        instrument::<E, _, _>("avg", async move {
+            fetch_aggregate(&expression, filter, executor).await
+        })
+    }
+}
+
+impl<E, T> Clone for Column<E, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, T> Copy for Column<E, T> {}
+
+impl<E, T> Debug for Column<E, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Column")
+            .field("name", &self.name)
+            .field("label", &self.label)
+            .finish()
+    }
+}
@@ -0,0 +1,141 @@
+//! This module streams the entities of a table using a server-side cursor, which fetches the
+//! rows in batches instead of holding the entire result at once (e.g. for exports).
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures_core::stream::BoxStream;
+use futures_util::stream;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{query_as, query_with, FromRow, Postgres, Result};
+
+use crate::filter::Filter;
+use crate::instrument::instrument;
+use crate::maybe_transaction::MaybeTransaction;
+use crate::traits::{
+    postgres_dialect::PostgresDialect, sql_describe::SqlDescribe, tenant_scoped::Unscoped,
+};
+
+/// The counter used to name the cursors, so that multiple ones can be open at once.
+static NEXT_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+/// This enum represents the phases of a cursor.
+enum Phase {
+    /// The cursor still has to be declared.
+    Declare,
+    /// The cursor is declared and may contain further rows.
+    Fetch,
+    /// The cursor is exhausted (or failed).
+    Done,
+}
+
+/// This struct contains the state of a stream using a cursor.
+struct CursorState<'a, 't, E> {
+    transaction: &'a mut MaybeTransaction<'t, Postgres>,
+    filter: Option<&'a Filter<E>>,
+    name: String,
+    batch_size: usize,
+    buffer: VecDeque<E>,
+    phase: Phase,
+}
+
+impl<'a, 't, E> CursorState<'a, 't, E>
+where
+    E: SqlDescribe + Send + Unpin + for<'r> FromRow<'r, PgRow>,
+{
+    /// This method declares the cursor selecting all entities matching the filter (if any).
+    async fn declare(&mut self) -> Result<()> {
+        let mut arguments = PgArguments::default();
+        let condition = match self.filter {
+            Some(filter) => {
+                filter.bind(&mut arguments);
+                filter.where_clause(1)
+            }
+            None => "".into(),
+        };
+        let sql = format!(
+            "DECLARE {} NO SCROLL CURSOR FOR SELECT {} FROM {} {};",
+            self.name,
+            E::select_list(),
+            E::table_name(),
+            condition
+        );
+
+        let transaction = self.transaction.get().await?;
+        query_with(&sql, arguments)
+            .execute(&mut *transaction)
+            .await?;
+        self.phase = Phase::Fetch;
+        Ok(())
+    }
+
+    /// This method fetches the next batch into the buffer, closing the cursor in case it is
+    /// exhausted.
+    async fn fetch(&mut self) -> Result<()> {
+        let sql = format!("FETCH {} FROM {};", self.batch_size, self.name);
+        let transaction = self.transaction.get().await?;
+        let batch: Vec<E> = query_as(&sql).fetch_all(&mut *transaction).await?;
+
+        if batch.len() < self.batch_size {
+            let sql = format!("CLOSE {};", self.name);
+            query_with(&sql, PgArguments::default())
+                .execute(&mut *transaction)
+                .await?;
+            self.phase = Phase::Done;
+        }
+        self.buffer.extend(batch);
+        Ok(())
+    }
+
+    /// This method returns the next entity (if any), declaring the cursor and fetching the
+    /// next batch as needed. After an error, the stream ends.
+    async fn next(&mut self) -> Option<Result<E>> {
+        loop {
+            if let Some(entity) = self.buffer.pop_front() {
+                return Some(Ok(entity));
+            }
+
+            let result = match self.phase {
+                Phase::Declare => instrument::<E, _, _>("declare_cursor", self.declare()).await,
+                Phase::Fetch => instrument::<E, _, _>("fetch_cursor", self.fetch()).await,
+                Phase::Done => return None,
+            };
+            if let Err(e) = result {
+                self.phase = Phase::Done;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// This function streams all entities matching the filter (if any) using a server-side cursor
+/// within the given transaction (which is opened if needed), fetching `batch_size` rows at
+/// once. The cursor is closed as soon as it is exhausted; in case the stream is dropped
+/// before, it is closed alongside the transaction.
+/// Just like `Entity::stream_with_cursor`, it requires an entity which isn't scoped to a
+/// tenant, as the cursor would select the rows of all tenants otherwise.
+pub fn stream_with_cursor<'a, 't, E>(
+    transaction: &'a mut MaybeTransaction<'t, Postgres>,
+    filter: Option<&'a Filter<E>>,
+    batch_size: usize,
+) -> BoxStream<'a, Result<E>>
+where
+    E: 'a + SqlDescribe + Unscoped + PostgresDialect + Send + Unpin + for<'r> FromRow<'r, PgRow>,
+{
+    let state = CursorState {
+        transaction,
+        filter,
+        name: format!(
+            "sqlx_extension_cursor_{}",
+            NEXT_CURSOR.fetch_add(1, Ordering::Relaxed)
+        ),
+        batch_size: batch_size.max(1),
+        buffer: VecDeque::new(),
+        phase: Phase::Declare,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        let next = state.next().await?;
+        Some((next, state))
+    }))
+}
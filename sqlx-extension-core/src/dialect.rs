@@ -0,0 +1,239 @@
+//! This module contains the SQL dialects the generated statements can be rendered for, as
+//! well as the validation of identifiers given within attributes.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The maximum length of an identifier (the limit of Postgres, which is the strictest one).
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+/// This enum represents the dialect of the database, which determines how identifiers are
+/// quoted and how placeholders are written. Executing the statements of MySQL or SQLite
+/// requires the respective cargo feature (`mysql` or `sqlite`); the functions executing
+/// filtered statements themselves (e.g. `Entity::delete_where`) bind their values for
+/// Postgres and are therefore only available for entities implementing `PostgresDialect`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Identifiers are quoted as `"x"`, placeholders are written as `$1`.
+    #[default]
+    Postgres,
+    /// Identifiers are quoted as `` `x` ``, placeholders are written as `?`.
+    MySql,
+    /// Identifiers are quoted as `"x"`, placeholders are written as `?1`.
+    Sqlite,
+}
+
+impl Dialect {
+    /// All supported dialects alongside their names within the attribute.
+    const ALL: [(&'static str, Dialect); 3] = [
+        ("postgres", Dialect::Postgres),
+        ("mysql", Dialect::MySql),
+        ("sqlite", Dialect::Sqlite),
+    ];
+
+    /// This method returns the character used to quote identifiers.
+    pub fn quote_char(&self) -> char {
+        match self {
+            Dialect::Postgres | Dialect::Sqlite => '"',
+            Dialect::MySql => '`',
+        }
+    }
+
+    /// This method quotes the given identifier, escaping the quote character within it.
+    pub fn quote_identifier(&self, identifier: &str) -> String {
+        let quote = self.quote_char();
+        let escaped = identifier.replace(quote, &format!("{}{}", quote, quote));
+        format!("{}{}{}", quote, escaped, quote)
+    }
+
+    /// This method returns the placeholder of the parameter with the given (1-based) index.
+    pub fn placeholder(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${}", index),
+            Dialect::MySql => "?".into(),
+            Dialect::Sqlite => format!("?{}", index),
+        }
+    }
+
+    /// This method returns the maximum number of placeholders a single statement may contain.
+    pub fn max_placeholders(&self) -> usize {
+        match self {
+            Dialect::Postgres | Dialect::MySql => 65535,
+            Dialect::Sqlite => 999,
+        }
+    }
+
+    /// This method returns whether statements may return the affected rows using `RETURNING`,
+    /// which MySQL doesn't support.
+    pub fn supports_returning(&self) -> bool {
+        !matches!(self, Dialect::MySql)
+    }
+
+    /// This method returns whether rows may be locked using e.g. `FOR UPDATE`, which SQLite
+    /// doesn't support (as it locks the entire database instead).
+    pub fn supports_row_locks(&self) -> bool {
+        !matches!(self, Dialect::Sqlite)
+    }
+
+    /// This method returns the placeholder of the parameter with the given (1-based) index,
+    /// cast to the given SQL type (if any), e.g. `$1::my_enum`.
+    pub fn typed_placeholder(&self, index: usize, sql_type: Option<&str>) -> String {
+        let placeholder = self.placeholder(index);
+        match (self, sql_type) {
+            (_, None) => placeholder,
+            (Dialect::Postgres, Some(sql_type)) => format!("{}::{}", placeholder, sql_type),
+            (_, Some(sql_type)) => format!("CAST({} AS {})", placeholder, sql_type),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    /// This implementation parses a dialect from its name, e.g. `postgres`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, dialect)| *dialect)
+            .ok_or_else(|| {
+                let names = Self::ALL.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                format!(
+                    "`{}` is not a valid dialect, expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// This constant is referenced by the code the `Describe` derive macro generates for a table
+/// qualified by its schema within the table attribute, so that a deprecation warning is
+/// emitted for it.
+#[doc(hidden)]
+#[deprecated(
+    note = "`#[table(\"schema.table\")]` is deprecated, use `#[schema(\"schema\")]` and `#[table(\"table\")]` instead"
+)]
+pub const SCHEMA_QUALIFIED_TABLE: () = ();
+
+/// This error is returned in case a value isn't a legal identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidIdentifier {
+    /// The rejected value.
+    pub identifier: String,
+    /// The reason why the value was rejected.
+    pub reason: &'static str,
+}
+
+impl Display for InvalidIdentifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a valid identifier: {}",
+            self.identifier.escape_debug(),
+            self.reason
+        )
+    }
+}
+
+impl StdError for InvalidIdentifier {}
+
+/// This function checks whether the given value is a legal identifier, i.e. a non-empty name
+/// of at most 63 bytes which contains neither control characters nor quotes.
+/// Dots are rejected as well, as a schema has to be given separately.
+pub fn validate_identifier(identifier: &str) -> Result<(), InvalidIdentifier> {
+    let reason = if identifier.is_empty() {
+        "it must not be empty"
+    } else if identifier.len() > MAX_IDENTIFIER_LENGTH {
+        "it must not be longer than 63 bytes"
+    } else if identifier
+        .chars()
+        .any(|c| c.is_control() || c == '"' || c == '`')
+    {
+        "it must not contain quotes or control characters"
+    } else if identifier.contains('.') {
+        "it must not contain a dot, the schema has to be given separately"
+    } else {
+        return Ok(());
+    };
+
+    Err(InvalidIdentifier {
+        identifier: identifier.into(),
+        reason,
+    })
+}
+
+/// This function checks whether the given value is a legal SQL type, which is interpolated
+/// into statements unquoted (e.g. `my_schema.my_enum` or `varchar(32)[]`).
+pub fn validate_type_name(type_name: &str) -> Result<(), InvalidIdentifier> {
+    let reason = if type_name.trim().is_empty() {
+        "it must not be empty"
+    } else if !type_name
+        .chars()
+        .all(|c| c.is_alphanumeric() || "_. ,()[]".contains(c))
+    {
+        "a type may only contain letters, digits, underscores, dots, spaces, commas, parentheses and brackets"
+    } else {
+        return Ok(());
+    };
+
+    Err(InvalidIdentifier {
+        identifier: type_name.into(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_identifiers_per_dialect() {
+        assert_eq!(Dialect::Postgres.quote_identifier("user"), "\"user\"");
+        assert_eq!(Dialect::MySql.quote_identifier("user"), "`user`");
+        assert_eq!(Dialect::Sqlite.quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn renders_placeholders_per_dialect() {
+        assert_eq!(Dialect::Postgres.placeholder(2), "$2");
+        assert_eq!(Dialect::MySql.placeholder(2), "?");
+        assert_eq!(Dialect::Sqlite.placeholder(2), "?2");
+        assert_eq!(
+            Dialect::Postgres.typed_placeholder(1, Some("kind_t")),
+            "$1::kind_t"
+        );
+        assert_eq!(
+            Dialect::MySql.typed_placeholder(1, Some("CHAR")),
+            "CAST(? AS CHAR)"
+        );
+        assert_eq!(Dialect::Sqlite.typed_placeholder(3, None), "?3");
+    }
+
+    #[test]
+    fn parses_dialects() {
+        assert_eq!("mysql".parse(), Ok(Dialect::MySql));
+        assert!("oracle".parse::<Dialect>().is_err());
+    }
+
+    #[test]
+    fn validates_identifiers() {
+        assert!(validate_identifier("user_id").is_ok());
+        assert!(validate_identifier("userId").is_ok());
+        assert!(validate_identifier("").is_err());
+        assert!(validate_identifier(&"a".repeat(64)).is_err());
+        assert!(validate_identifier("a\"b").is_err());
+        assert!(validate_identifier("a`b").is_err());
+        assert!(validate_identifier("a\nb").is_err());
+        assert!(validate_identifier("schema.table").is_err());
+    }
+
+    #[test]
+    fn validates_type_names() {
+        assert!(validate_type_name("my_schema.my_enum").is_ok());
+        assert!(validate_type_name("varchar(32)[]").is_ok());
+        assert!(validate_type_name(" ").is_err());
+        assert!(validate_type_name("text; DROP TABLE x").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Not;
+
+use sqlx::encode::IsNull;
+use sqlx::postgres::{PgArgumentBuffer, PgArguments};
+use sqlx::{Arguments, Encode, Postgres, Type};
+
+use crate::traits::sql_describe::SqlDescribe;
+
+/// This enum represents all operators a single column can be filtered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    IsNull,
+    IsNotNull,
+}
+
+impl Operator {
+    /// This method returns the SQL representation of the operator.
+    pub fn sql(&self) -> &'static str {
+        match self {
+            Operator::Eq => "=",
+            Operator::Ne => "<>",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::IsNull => "IS NULL",
+            Operator::IsNotNull => "IS NOT NULL",
+        }
+    }
+
+    /// This method returns whether the operator compares the column to a bound value.
+    pub fn takes_value(&self) -> bool {
+        !matches!(self, Operator::IsNull | Operator::IsNotNull)
+    }
+}
+
+/// This trait represents the condition of a single column: it can both bind the compared
+/// value to a statement and evaluate the condition in memory.
+trait Condition<E>: Send + Sync {
+    /// This method binds the compared value (if any) to the given arguments.
+    fn bind_to(&self, arguments: &mut PgArguments);
+
+    /// This method evaluates the condition for the given entity. In case the result is
+    /// unknown (e.g. as the column is compared to `NULL`), `None` is returned.
+    fn matches(&self, entity: &E) -> Option<bool>;
+}
+
+/// The function evaluating a comparison in memory.
+type Comparator<E, T> = Box<dyn Fn(&E, &T) -> Option<bool> + Send + Sync>;
+
+/// A condition comparing a column to a given value.
+struct Comparison<E, T> {
+    value: T,
+    matches: Comparator<E, T>,
+}
+
+impl<E, T> Condition<E> for Comparison<E, T>
+where
+    T: Send + Sync + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+{
+    fn bind_to(&self, arguments: &mut PgArguments) {
+        arguments.add(&self.value);
+    }
+
+    fn matches(&self, entity: &E) -> Option<bool> {
+        (self.matches)(entity, &self.value)
+    }
+}
+
+/// A condition which doesn't require a value (e.g. `IS NULL`).
+struct Check<E> {
+    matches: Box<dyn Fn(&E) -> bool + Send + Sync>,
+}
+
+impl<E> Condition<E> for Check<E> {
+    fn bind_to(&self, _arguments: &mut PgArguments) {}
+
+    fn matches(&self, entity: &E) -> Option<bool> {
+        Some((self.matches)(entity))
+    }
+}
+
+/// All nodes a filter expression consists of.
+enum Node<E> {
+    All,
+    Leaf {
+        column: &'static str,
+        operator: Operator,
+        sql_type: Option<&'static str>,
+        condition: Box<dyn Condition<E>>,
+    },
+    And(Vec<Filter<E>>),
+    Or(Vec<Filter<E>>),
+    Not(Box<Filter<E>>),
+}
+
+/// This struct represents a filter expression over the columns of an entity. It can be
+/// compiled into a SQL condition as well as evaluated in memory against an entity.
+/// Filters are usually created using the column constants generated by the `Describe`
+/// derive macro, e.g. `User::NAME.eq("Martin").and(User::AGE.ge(18))`.
+pub struct Filter<E> {
+    node: Node<E>,
+}
+
+impl<E: 'static> Filter<E> {
+    /// This function creates a filter which matches every entity.
+    pub fn all() -> Self {
+        Self { node: Node::All }
+    }
+
+    /// This function creates a filter comparing the given column to a value using the
+    /// passed operator. The closure is used to evaluate the filter in memory; it returns
+    /// `None` in case the result is unknown, i.e. in case either side is `NULL`.
+    pub fn compare<T>(
+        column: &'static str,
+        operator: Operator,
+        value: T,
+        matches: impl Fn(&E, &T) -> Option<bool> + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: 'static + Send + Sync + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+    {
+        Self {
+            node: Node::Leaf {
+                column,
+                operator,
+                sql_type: None,
+                condition: Box::new(Comparison {
+                    value,
+                    matches: Box::new(matches),
+                }),
+            },
+        }
+    }
+
+    /// This function creates a filter checking the given column using an operator which
+    /// doesn't take a value. The closure is used to evaluate the filter in memory.
+    pub fn check(
+        column: &'static str,
+        operator: Operator,
+        matches: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            node: Node::Leaf {
+                column,
+                operator,
+                sql_type: None,
+                condition: Box::new(Check {
+                    matches: Box::new(matches),
+                }),
+            },
+        }
+    }
+
+    /// This method casts the compared value of a single comparison to the given SQL type
+    /// (if any), e.g. `"kind" = $1::my_enum`.
+    pub(crate) fn cast_to(mut self, cast: Option<&'static str>) -> Self {
+        if let Node::Leaf { sql_type, .. } = &mut self.node {
+            *sql_type = cast;
+        }
+        self
+    }
+
+    /// This method combines both filters, requiring both of them to match.
+    pub fn and(self, other: Filter<E>) -> Self {
+        match (self.node, other.node) {
+            (Node::All, node) | (node, Node::All) => Self { node },
+            (Node::And(mut filters), node) => {
+                filters.push(Self { node });
+                Self {
+                    node: Node::And(filters),
+                }
+            }
+            (node, other) => Self {
+                node: Node::And(vec![Self { node }, Self { node: other }]),
+            },
+        }
+    }
+
+    /// This method combines both filters, requiring at least one of them to match.
+    pub fn or(self, other: Filter<E>) -> Self {
+        match (self.node, other.node) {
+            (Node::All, _) | (_, Node::All) => Self::all(),
+            (Node::Or(mut filters), node) => {
+                filters.push(Self { node });
+                Self {
+                    node: Node::Or(filters),
+                }
+            }
+            (node, other) => Self {
+                node: Node::Or(vec![Self { node }, Self { node: other }]),
+            },
+        }
+    }
+}
+
+impl<E> Filter<E> {
+    /// This method returns whether the filter matches every entity by construction, i.e.
+    /// whether it lacks any restricting condition (e.g. `Filter::all()`, `!!Filter::all()` or
+    /// a disjunction containing `Filter::all()`).
+    pub fn is_all(&self) -> bool {
+        match &self.node {
+            Node::All => true,
+            Node::Leaf { .. } => false,
+            Node::And(filters) => filters.iter().all(Filter::is_all),
+            Node::Or(filters) => filters.iter().any(Filter::is_all),
+            Node::Not(filter) => match &filter.node {
+                Node::Not(filter) => filter.is_all(),
+                _ => false,
+            },
+        }
+    }
+
+    /// This method returns the number of placeholders the compiled filter contains.
+    pub fn placeholder_count(&self) -> usize {
+        match &self.node {
+            Node::All => 0,
+            Node::Leaf { operator, .. } => operator.takes_value() as usize,
+            Node::And(filters) | Node::Or(filters) => {
+                filters.iter().map(Filter::placeholder_count).sum()
+            }
+            Node::Not(filter) => filter.placeholder_count(),
+        }
+    }
+
+    /// This method binds all values of the filter to the given arguments, in the order of
+    /// the placeholders produced by `to_sql`.
+    pub fn bind(&self, arguments: &mut PgArguments) {
+        match &self.node {
+            Node::All => {}
+            Node::Leaf { condition, .. } => condition.bind_to(arguments),
+            Node::And(filters) | Node::Or(filters) => {
+                filters.iter().for_each(|f| f.bind(arguments))
+            }
+            Node::Not(filter) => filter.bind(arguments),
+        }
+    }
+
+    /// This method evaluates the filter in memory for the given entity, following the
+    /// three-valued logic of SQL: comparing a column to `NULL` (e.g. using `eq(None)`) is
+    /// unknown, just like its negation, so the entity matches just like it would be returned
+    /// by the database.
+    pub fn matches(&self, entity: &E) -> bool {
+        self.evaluate(entity) == Some(true)
+    }
+
+    /// This utility method evaluates the filter for the given entity, returning `None` in case
+    /// the result is unknown.
+    fn evaluate(&self, entity: &E) -> Option<bool> {
+        match &self.node {
+            Node::All => Some(true),
+            Node::Leaf { condition, .. } => condition.matches(entity),
+            Node::And(filters) => Self::combine(filters, entity, false),
+            Node::Or(filters) => Self::combine(filters, entity, true),
+            Node::Not(filter) => filter.evaluate(entity).map(|result| !result),
+        }
+    }
+
+    /// This utility method evaluates the filters combined using `AND` (in case `decisive` is
+    /// `false`) or `OR` (in case it is `true`): any filter evaluating to `decisive` determines
+    /// the result, otherwise it is unknown in case any filter is unknown.
+    fn combine(filters: &[Filter<E>], entity: &E, decisive: bool) -> Option<bool> {
+        let mut result = Some(!decisive);
+        for filter in filters {
+            match filter.evaluate(entity) {
+                Some(value) if value == decisive => return Some(decisive),
+                Some(_) => {}
+                None => result = None,
+            }
+        }
+        result
+    }
+}
+
+impl<E: SqlDescribe> Filter<E> {
+    /// This method compiles the filter into a SQL condition, numbering the placeholders
+    /// starting at `first_placeholder` using the dialect of the entity.
+    pub fn to_sql(&self, first_placeholder: usize) -> String {
+        let mut sql = String::new();
+        let mut next_placeholder = first_placeholder;
+        self.write_sql(&mut sql, &mut next_placeholder);
+        sql
+    }
+
+    /// This method compiles the filter into a `WHERE` clause, numbering the placeholders
+    /// starting at `first_placeholder` using the dialect of the entity. In case the filter
+    /// matches all entities, an empty string is returned.
+    pub fn where_clause(&self, first_placeholder: usize) -> String {
+        if self.is_all() {
+            "".into()
+        } else {
+            format!("WHERE {}", self.to_sql(first_placeholder))
+        }
+    }
+
+    /// This utility method writes the SQL of the current node into the given string.
+    fn write_sql(&self, sql: &mut String, next_placeholder: &mut usize) {
+        match &self.node {
+            Node::All => sql.push_str("TRUE"),
+            Node::Leaf {
+                column,
+                operator,
+                sql_type,
+                ..
+            } => {
+                if operator.takes_value() {
+                    sql.push_str(&format!(
+                        "{} {} {}",
+                        column,
+                        operator.sql(),
+                        E::dialect().typed_placeholder(*next_placeholder, *sql_type)
+                    ));
+                    *next_placeholder += 1;
+                } else {
+                    sql.push_str(&format!("{} {}", column, operator.sql()));
+                }
+            }
+            Node::And(filters) => Self::write_joined(filters, " AND ", sql, next_placeholder),
+            Node::Or(filters) => Self::write_joined(filters, " OR ", sql, next_placeholder),
+            Node::Not(filter) => {
+                sql.push_str("NOT (");
+                filter.write_sql(sql, next_placeholder);
+                sql.push(')');
+            }
+        }
+    }
+
+    /// This utility method writes all passed filters in parentheses, separated by the given
+    /// separator.
+    fn write_joined(
+        filters: &[Filter<E>],
+        separator: &str,
+        sql: &mut String,
+        next_placeholder: &mut usize,
+    ) {
+        for (index, filter) in filters.iter().enumerate() {
+            if index > 0 {
+                sql.push_str(separator);
+            }
+            sql.push('(');
+            filter.write_sql(sql, next_placeholder);
+            sql.push(')');
+        }
+    }
+}
+
+impl<E> Not for Filter<E> {
+    type Output = Filter<E>;
+
+    /// This implementation negates the filter, cancelling out a double negation.
+    fn not(self) -> Self::Output {
+        match self.node {
+            Node::Not(filter) => *filter,
+            node => Self {
+                node: Node::Not(Box::new(Self { node })),
+            },
+        }
+    }
+}
+
+impl<E: SqlDescribe> Debug for Filter<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Filter").field(&self.to_sql(1)).finish()
+    }
+}
+
+/// This error is returned by bulk statements (e.g. `Entity::delete_where`) in case the filter
+/// matches every entity, as the statement would affect the entire table (see `BulkError`).
+/// The unrestricted variants (e.g. `Entity::delete_all`) have to be used in case this is
+/// intended.
+#[derive(Debug)]
+pub struct UnrestrictedFilter {
+    table: &'static str,
+}
+
+impl UnrestrictedFilter {
+    /// This function creates the error for the given table.
+    pub fn new(table: &'static str) -> Self {
+        Self { table }
+    }
+
+    /// This method returns the (qualified) name of the table the statement targeted.
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+}
+
+impl Display for UnrestrictedFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "refusing to affect every row of {} using a filter without conditions",
+            self.table
+        )
+    }
+}
+
+impl StdError for UnrestrictedFilter {}
+
+/// This enum represents all errors which can occur while executing a bulk statement
+/// restricted by a filter (e.g. `Entity::delete_where`).
+#[derive(Debug)]
+pub enum BulkError {
+    /// The database returned an error.
+    Database(sqlx::Error),
+    /// The filter matches every entity, so the statement was refused.
+    Unrestricted(UnrestrictedFilter),
+}
+
+impl Display for BulkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BulkError::Database(e) => write!(f, "bulk statement failed: {}", e),
+            BulkError::Unrestricted(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl StdError for BulkError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            BulkError::Database(e) => Some(e),
+            BulkError::Unrestricted(e) => Some(e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for BulkError {
+    fn from(e: sqlx::Error) -> Self {
+        BulkError::Database(e)
+    }
+}
+
+/// This function returns whether the given value is encoded as `NULL` (e.g. `None`).
+pub(crate) fn is_null<T: for<'q> Encode<'q, Postgres>>(value: &T) -> bool {
+    matches!(
+        value.encode_by_ref(&mut PgArgumentBuffer::default()),
+        IsNull::Yes
+    )
+}
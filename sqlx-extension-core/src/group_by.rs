@@ -0,0 +1,162 @@
+//! This module contains a builder for `GROUP BY` queries over the table of an entity, whose
+//! rows are decoded into arbitrary structs (e.g. the rows of a report).
+
+use std::fmt::{self, Debug, Formatter};
+
+use futures_core::future::BoxFuture;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{query_as_with, Executor, FromRow, Postgres, Result};
+
+use crate::aggregate::Numeric;
+use crate::column::Column;
+use crate::filter::Filter;
+use crate::instrument::instrument;
+use crate::traits::{
+    postgres_dialect::PostgresDialect, sql_describe::SqlDescribe, tenant_scoped::Unscoped,
+};
+
+/// The selected number of rows of each group.
+const COUNT: &str = "COUNT(*) AS \"count\"";
+
+/// This struct builds a query grouping the rows of the table of an entity by the given
+/// columns, selecting these columns alongside the given aggregates. The grouped columns are
+/// selected using their labels (i.e. the names of the columns), while the aggregates are
+/// aliased by the name of the function followed by the label of the column (e.g. `sum_qty`)
+/// or `count`, so the rows can be decoded into any struct with these fields.
+pub struct GroupBy<E> {
+    select: Vec<String>,
+    group_by: Vec<&'static str>,
+    filter: Filter<E>,
+}
+
+impl<E> GroupBy<E>
+where
+    E: SqlDescribe + Unscoped + PostgresDialect + 'static,
+{
+    /// This function creates an empty query over all rows of the table of the entity.
+    pub fn new() -> Self {
+        Self {
+            select: Vec::new(),
+            group_by: Vec::new(),
+            filter: Filter::all(),
+        }
+    }
+
+    /// This method groups the rows by the given column, which is selected as well.
+    pub fn by<T>(mut self, column: Column<E, T>) -> Self {
+        self.select.push(format!(
+            "{} AS {}",
+            column.name(),
+            E::dialect().quote_identifier(column.label())
+        ));
+        self.group_by.push(column.name());
+        self
+    }
+
+    /// This method restricts the grouped rows to the ones matching the given filter. In case
+    /// it is called multiple times, all filters have to match.
+    pub fn filter(mut self, filter: Filter<E>) -> Self {
+        self.filter = self.filter.and(filter);
+        self
+    }
+
+    /// This method selects the number of rows of each group as `count`.
+    pub fn count(mut self) -> Self {
+        self.select.push(COUNT.into());
+        self
+    }
+
+    /// This method selects the sum of the given column as `sum_<label>`, cast to
+    /// `Numeric::SUM_TYPE`.
+    pub fn sum<T: Numeric>(self, column: Column<E, T>) -> Self {
+        let expression = format!("CAST(SUM({}) AS {})", column.name(), T::SUM_TYPE);
+        self.aggregate("sum", expression, column)
+    }
+
+    /// This method selects the average of the given column as `avg_<label>`, cast to
+    /// `DOUBLE PRECISION`.
+    pub fn avg<T: Numeric>(self, column: Column<E, T>) -> Self {
+        let expression = format!("CAST(AVG({}) AS DOUBLE PRECISION)", column.name());
+        self.aggregate("avg", expression, column)
+    }
+
+    /// This method selects the minimum of the given column as `min_<label>`.
+    pub fn min<T>(self, column: Column<E, T>) -> Self {
+        let expression = format!("MIN({})", column.name());
+        self.aggregate("min", expression, column)
+    }
+
+    /// This method selects the maximum of the given column as `max_<label>`.
+    pub fn max<T>(self, column: Column<E, T>) -> Self {
+        let expression = format!("MAX({})", column.name());
+        self.aggregate("max", expression, column)
+    }
+
+    /// This method renders the query; the placeholders of the filter start at `$1`. In case
+    /// neither a column nor an aggregate has been selected, the number of rows is selected as
+    /// `count` (just like using `count`).
+    pub fn sql(&self) -> String {
+        let select = if self.select.is_empty() {
+            COUNT.to_string()
+        } else {
+            self.select.join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", select, E::table_name());
+        if !self.filter.is_all() {
+            sql.push(' ');
+            sql.push_str(&self.filter.where_clause(1));
+        }
+        if !self.group_by.is_empty() {
+            sql.push_str(" GROUP BY ");
+            sql.push_str(&self.group_by.join(", "));
+        }
+        sql.push(';');
+        sql
+    }
+
+    /// This method executes the query, decoding every group into the given type.
+    pub fn fetch_all<'a, 'c, R, X>(&'a self, executor: X) -> BoxFuture<'a, Result<Vec<R>>>
+    where
+        'c: 'a,
+        R: Send + Unpin + for<'r> FromRow<'r, PgRow>,
+        X: 'a + Executor<'c, Database = Postgres>,
+    {
+        instrument::<E, _, _>("group_by", async move {
+            let sql = self.sql();
+            let mut arguments = PgArguments::default();
+            self.filter.bind(&mut arguments);
+
+            query_as_with(&sql, arguments).fetch_all(executor).await
+        })
+    }
+
+    /// This utility method selects the given aggregate of the column, aliased by the name of
+    /// the function followed by the label of the column.
+    fn aggregate<T>(mut self, function: &str, expression: String, column: Column<E, T>) -> Self {
+        let alias = format!("{}_{}", function, column.label());
+        self.select.push(format!(
+            "{} AS {}",
+            expression,
+            E::dialect().quote_identifier(&alias)
+        ));
+        self
+    }
+}
+
+impl<E> Default for GroupBy<E>
+where
+    E: SqlDescribe + Unscoped + PostgresDialect + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Debug for GroupBy<E>
+where
+    E: SqlDescribe + Unscoped + PostgresDialect + 'static,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GroupBy").field(&self.sql()).finish()
+    }
+}
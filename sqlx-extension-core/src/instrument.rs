@@ -0,0 +1,286 @@
+//! This module instruments the operations executed by the library (e.g. `Changeset::apply`).
+//! With the `tracing` feature, every operation runs inside a span carrying the name of the
+//! entity, its table, the kind of the operation, the executed statement (if known) and its
+//! duration; operations exceeding the slow query threshold are reported as warnings. Without
+//! it, operations are merely executed.
+//!
+//! The functions which merely return a query (e.g. `Entity::retrieve_by_pk`) can't be
+//! instrumented by themselves, so they are executed using the helpers of this module instead
+//! (e.g. `instrument::fetch_optional::<User, _, _, _, _>("retrieve_by_pk", query, &pool)`).
+
+#[cfg(feature = "tracing")]
+use std::convert::TryFrom;
+use std::future::Future;
+#[cfg(feature = "tracing")]
+use std::pin::Pin;
+#[cfg(feature = "tracing")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "tracing")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
+use sqlx::query::{Query, QueryAs};
+use sqlx::{Database, Execute, Executor, FromRow, IntoArguments, Result};
+
+use crate::traits::sql_describe::SqlDescribe;
+
+/// The threshold above which operations are reported as slow (in microseconds).
+#[cfg(feature = "tracing")]
+static SLOW_QUERY_THRESHOLD: AtomicU64 = AtomicU64::new(1_000_000);
+
+/// This function sets the duration above which operations are reported as slow (one second by
+/// default). Without the `tracing` feature, nothing is reported at all.
+#[cfg(feature = "tracing")]
+pub fn set_slow_query_threshold(threshold: Duration) {
+    let micros = u64::try_from(threshold.as_micros()).unwrap_or(u64::MAX);
+    SLOW_QUERY_THRESHOLD.store(micros, Ordering::Relaxed);
+}
+
+/// Without the `tracing` feature, the threshold is ignored.
+#[cfg(not(feature = "tracing"))]
+pub fn set_slow_query_threshold(_threshold: Duration) {}
+
+/// This function returns the duration above which operations are reported as slow.
+#[cfg(feature = "tracing")]
+pub fn slow_query_threshold() -> Duration {
+    Duration::from_micros(SLOW_QUERY_THRESHOLD.load(Ordering::Relaxed))
+}
+
+/// Without the `tracing` feature, no operation is reported as slow.
+#[cfg(not(feature = "tracing"))]
+pub fn slow_query_threshold() -> Duration {
+    Duration::MAX
+}
+
+/// This function executes the given operation of the entity inside a span named
+/// `sqlx_extension`, recording its duration once it completes. Failed operations are reported
+/// as errors, slow ones as warnings.
+pub fn instrument<'a, E, T, F>(operation: &'static str, future: F) -> BoxFuture<'a, Result<T>>
+where
+    E: SqlDescribe + ?Sized,
+    F: 'a + Send + Future<Output = Result<T>>,
+{
+    instrument_statement::<E, T, F>(operation, None, future)
+}
+
+/// This function executes the given operation of the entity just like `instrument`, recording
+/// the executed statement within the span as well.
+#[cfg(feature = "tracing")]
+pub fn instrument_statement<'a, E, T, F>(
+    operation: &'static str,
+    statement: Option<&str>,
+    future: F,
+) -> BoxFuture<'a, Result<T>>
+where
+    E: SqlDescribe + ?Sized,
+    F: 'a + Send + Future<Output = Result<T>>,
+{
+    use tracing::Instrument;
+
+    let span = create_span::<E>(operation, statement);
+
+    Box::pin(async move {
+        let start = Instant::now();
+        let result = future.instrument(span.clone()).await;
+        finish(&span, start, result.as_ref().err());
+
+        result
+    })
+}
+
+/// Without the `tracing` feature, the operation is merely executed.
+#[cfg(not(feature = "tracing"))]
+pub fn instrument_statement<'a, E, T, F>(
+    _operation: &'static str,
+    _statement: Option<&str>,
+    future: F,
+) -> BoxFuture<'a, Result<T>>
+where
+    E: SqlDescribe + ?Sized,
+    F: 'a + Send + Future<Output = Result<T>>,
+{
+    Box::pin(future)
+}
+
+/// This function executes the given query (e.g. `Entity::update_by_pk`) as the given
+/// operation of the entity (see `instrument_statement`).
+pub fn execute<'q, 'c, E, DB, A, X>(
+    operation: &'static str,
+    query: Query<'q, DB, A>,
+    executor: X,
+) -> BoxFuture<'q, Result<DB::QueryResult>>
+where
+    'c: 'q,
+    E: SqlDescribe + ?Sized,
+    DB: Database,
+    A: 'q + Send + IntoArguments<'q, DB>,
+    X: 'q + Executor<'c, Database = DB>,
+{
+    let statement = query.sql();
+    instrument_statement::<E, _, _>(operation, Some(statement), query.execute(executor))
+}
+
+/// This function fetches exactly one row using the given query (e.g. `Entity::insert_without_pk`)
+/// as the given operation of the entity (see `instrument_statement`).
+pub fn fetch_one<'q, 'c, E, DB, O, A, X>(
+    operation: &'static str,
+    query: QueryAs<'q, DB, O, A>,
+    executor: X,
+) -> BoxFuture<'q, Result<O>>
+where
+    'c: 'q,
+    E: SqlDescribe + ?Sized,
+    DB: Database,
+    O: 'q + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+    A: 'q + Send + IntoArguments<'q, DB>,
+    X: 'q + Executor<'c, Database = DB>,
+{
+    let statement = query.sql();
+    instrument_statement::<E, _, _>(operation, Some(statement), query.fetch_one(executor))
+}
+
+/// This function fetches at most one row using the given query (e.g. `Entity::retrieve_by_pk`)
+/// as the given operation of the entity (see `instrument_statement`).
+pub fn fetch_optional<'q, 'c, E, DB, O, A, X>(
+    operation: &'static str,
+    query: QueryAs<'q, DB, O, A>,
+    executor: X,
+) -> BoxFuture<'q, Result<Option<O>>>
+where
+    'c: 'q,
+    E: SqlDescribe + ?Sized,
+    DB: Database,
+    O: 'q + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+    A: 'q + Send + IntoArguments<'q, DB>,
+    X: 'q + Executor<'c, Database = DB>,
+{
+    let statement = query.sql();
+    instrument_statement::<E, _, _>(operation, Some(statement), query.fetch_optional(executor))
+}
+
+/// This function fetches all rows using the given query (e.g. `Entity::retrieve_all`) as the
+/// given operation of the entity (see `instrument_statement`).
+pub fn fetch_all<'q, 'c, E, DB, O, A, X>(
+    operation: &'static str,
+    query: QueryAs<'q, DB, O, A>,
+    executor: X,
+) -> BoxFuture<'q, Result<Vec<O>>>
+where
+    'c: 'q,
+    E: SqlDescribe + ?Sized,
+    DB: Database,
+    O: 'q + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+    A: 'q + Send + IntoArguments<'q, DB>,
+    X: 'q + Executor<'c, Database = DB>,
+{
+    let statement = query.sql();
+    instrument_statement::<E, _, _>(operation, Some(statement), query.fetch_all(executor))
+}
+
+/// This function streams the rows of the given statement as the given operation of the entity.
+/// The span is entered whenever the stream is polled; its duration is recorded once the
+/// stream is exhausted.
+#[cfg(feature = "tracing")]
+pub fn instrument_stream<'a, E, T>(
+    operation: &'static str,
+    statement: Option<&str>,
+    stream: BoxStream<'a, Result<T>>,
+) -> BoxStream<'a, Result<T>>
+where
+    E: SqlDescribe + ?Sized,
+    T: 'a,
+{
+    Box::pin(InstrumentedStream {
+        stream,
+        span: create_span::<E>(operation, statement),
+        start: Instant::now(),
+    })
+}
+
+/// Without the `tracing` feature, the stream is merely returned.
+#[cfg(not(feature = "tracing"))]
+pub fn instrument_stream<'a, E, T>(
+    _operation: &'static str,
+    _statement: Option<&str>,
+    stream: BoxStream<'a, Result<T>>,
+) -> BoxStream<'a, Result<T>>
+where
+    E: SqlDescribe + ?Sized,
+{
+    stream
+}
+
+/// This struct wraps a stream, entering the span of the operation whenever it is polled.
+#[cfg(feature = "tracing")]
+struct InstrumentedStream<'a, T> {
+    stream: BoxStream<'a, Result<T>>,
+    span: tracing::Span,
+    start: Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl<'a, T> futures_core::Stream for InstrumentedStream<'a, T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let next = {
+            let _entered = this.span.enter();
+            this.stream.as_mut().poll_next(cx)
+        };
+
+        match &next {
+            Poll::Ready(Some(Err(e))) => finish(&this.span, this.start, Some(e)),
+            Poll::Ready(None) => finish(&this.span, this.start, None),
+            _ => {}
+        }
+        next
+    }
+}
+
+/// This utility function creates the span of the given operation of the entity, recording the
+/// executed statement (if known).
+#[cfg(feature = "tracing")]
+fn create_span<E: SqlDescribe + ?Sized>(
+    operation: &'static str,
+    statement: Option<&str>,
+) -> tracing::Span {
+    use tracing::field;
+
+    let span = tracing::info_span!(
+        "sqlx_extension",
+        entity = std::any::type_name::<E>(),
+        table = E::table_name(),
+        operation,
+        statement = field::Empty,
+        duration_ms = field::Empty,
+    );
+    if let Some(statement) = statement {
+        span.record("statement", statement);
+    }
+    span
+}
+
+/// This utility function records the duration of the operation started at the given instant,
+/// reporting the error (if any) as well as whether the operation was slow.
+#[cfg(feature = "tracing")]
+fn finish(span: &tracing::Span, start: Instant, error: Option<&sqlx::Error>) {
+    let elapsed = start.elapsed();
+    span.record("duration_ms", elapsed.as_millis() as u64);
+
+    if let Some(e) = error {
+        tracing::error!(parent: span, error = %e, "operation failed");
+    }
+    let threshold = slow_query_threshold();
+    if elapsed > threshold {
+        tracing::warn!(
+            parent: span,
+            threshold_ms = threshold.as_millis() as u64,
+            "slow query"
+        );
+    }
+}
@@ -1,7 +1,32 @@
 //! This library defines types with which both the macro as well as the normal crate have to work
 //! with.
 
+pub mod aggregate;
+pub mod catalog;
+pub mod cipher;
+pub mod column;
+pub mod cursor;
+pub mod dialect;
+pub mod filter;
+pub mod group_by;
+pub mod instrument;
 pub mod models;
 pub mod query_builder;
 pub mod traits;
 pub mod maybe_transaction;
+pub mod outbox;
+pub mod repository;
+#[cfg(feature = "check")]
+pub mod schema;
+pub mod statements;
+pub mod tenant;
+pub mod tracked;
+pub mod verify;
+
+/// Re-exported so the code generated by the derive macros doesn't depend on the `sqlx`
+/// version of the user.
+pub use sqlx;
+
+/// Re-exported so the code generated by the derive macros can register the statements of
+/// every entity within the statement catalog.
+pub use inventory;
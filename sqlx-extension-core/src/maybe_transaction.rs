@@ -10,7 +10,7 @@ pub enum MaybeTransaction<'a, DB: Database> {
 impl<'a, DB: Database> MaybeTransaction<'a, DB> {
     /// This function retrieves the underlying transaction in case it was already opened
     /// or opens a new one in case a pool was contained.
-    pub async fn get(&'a mut self) -> Result<&'a mut Transaction<'a, DB>> {
+    pub async fn get(&mut self) -> Result<&mut Transaction<'a, DB>> {
         match self {
             MaybeTransaction::Transaction(ref mut transaction) => Ok(transaction),
             MaybeTransaction::Pool(pool) => {
@@ -1,6 +1,13 @@
-use std::convert::TryFrom;
+use std::fmt::Display;
 
-use syn::{Ident, Type};
+use proc_macro2::TokenStream;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Ident, Lit, LitStr, Meta, MetaNameValue, Token, Type};
+
+use crate::dialect::{validate_identifier, validate_type_name, Dialect, InvalidIdentifier};
+use crate::models::rename_rule::RenameRule;
+use crate::query_builder::render_expression;
 
 /// This enum represents an actual attribute on a field.
 #[derive(Debug, PartialEq, Eq)]
@@ -9,12 +16,81 @@ pub enum Attribute {
     /// new name of the field.
     Rename { new_name: String },
     /// The table attribute: it contains the name
-    /// of the table.
+    /// of the table. The deprecated form `#[table("schema.table")]` qualifies the table by
+    /// its schema, which should be given using the schema attribute instead.
     Table { table_name: String },
+    /// The schema attribute: it contains the name of the
+    /// schema the table belongs to.
+    Schema { schema_name: String },
     /// The current field should be marked as a primary key.
     PrimaryKey,
-    /// The type chosen for the identifier (also derives `Entity`!)
-    Identifier { ident_type: Type },
+    /// The columns of the current field (whose type derives `Describe` as well) are embedded
+    /// into the entity, each prefixed by the optional prefix (e.g. `#[flatten("home_")]`).
+    /// The entity can't derive `sqlx::FromRow` then, but has to request the implementation of
+    /// the derive macro using `#[describe(from_row)]`.
+    Flatten { prefix: Option<String> },
+    /// The placeholders of the current field are cast to the given SQL type, e.g.
+    /// `#[sql_type("my_enum")]` renders `$1::my_enum` (required for Postgres enums).
+    SqlType { type_name: String },
+    /// The column of the current field is selected using the given expression, in which `{}`
+    /// is replaced by the quoted column (e.g. `#[read_expr("ST_AsText({})")]`). The result is
+    /// aliased by the name of the column. Literal braces are written as `{{` and `}}`.
+    ReadExpr { expression: String },
+    /// The placeholders of the current field within `VALUES` and `SET` are wrapped in the
+    /// given expression, in which the single `{}` is replaced by the placeholder
+    /// (e.g. `#[write_expr("lower({})")]`). Literal braces are written as `{{` and `}}`
+    /// (e.g. `#[write_expr("coalesce({}, '{{}}'::jsonb)")]`).
+    WriteExpr { expression: String },
+    /// The current field is stored as JSON: it is bound and decoded via `sqlx::types::Json`,
+    /// so its type only has to implement `Serialize` and `Deserialize`. The entity can't
+    /// derive `sqlx::FromRow` then, but has to use `#[describe(from_row)]`.
+    Json,
+    /// The current field is encrypted using the registered `FieldCipher` whenever it is
+    /// bound and decrypted whenever it is decoded, so its column has to store bytes. The
+    /// entity can't derive `sqlx::FromRow` then, but has to use `#[describe(from_row)]`.
+    Encrypted,
+    /// The value of the current field is generated by the database (e.g. by a default or as
+    /// a generated column), so it is neither inserted by `insert_without_pk` nor updated by
+    /// `update_by_pk`, but merely returned.
+    Generated,
+    /// The current field contains the tenant a row belongs to; all generated retrieve,
+    /// update and delete statements are restricted to a single tenant.
+    Tenant,
+    /// The type chosen for the identifier (also derives `Entity`!). It can either be given
+    /// as a type (`#[ident(MyKey)]`) or as a string literal (`#[ident("MyKey")]`). The type
+    /// has to implement `From` for the tuple of all primary key fields (e.g. `From<(i64,)>`),
+    /// and `BindArguments` to be used with `PgRepository` or `Entity::exists_by_pk`.
+    Identifier { ident_type: Box<Type> },
+    /// The struct-level options of the `Describe` derive, e.g. `#[describe(check)]`.
+    Describe { options: Vec<DescribeOption> },
+}
+
+/// This enum represents a single option within the `describe` attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DescribeOption {
+    /// The generated statements should be verified against the schema of the database at
+    /// compile time (requires the `check` feature).
+    Check,
+    /// The case-conversion strategy applied to all fields without an explicit rename
+    /// (e.g. `#[describe(rename_all = "camelCase")]`).
+    RenameAll(RenameRule),
+    /// The dialect the statements are rendered for (e.g. `#[describe(dialect = "mysql")]`),
+    /// which defaults to Postgres.
+    Dialect(Dialect),
+    /// The derive macro implements `FromRow`, decoding flattened, JSON and encrypted fields
+    /// (which the derive of sqlx can't), so the struct must not derive `sqlx::FromRow`.
+    FromRow,
+    /// The derive macro generates a changeset (e.g. `UserChangeset`) for the struct, which
+    /// requires at least one field annotated with `p_key` and a dialect supporting `RETURNING`
+    /// (i.e. not MySQL).
+    Changeset,
+    /// The derive macro generates an insert DTO (e.g. `NewUser`) for the struct, containing
+    /// every field neither annotated with `p_key` nor `generated`, which requires a dialect
+    /// supporting `RETURNING` (i.e. not MySQL).
+    New,
+    /// The derive macro doesn't register the statements of the struct within the statement
+    /// catalog (see `catalog`), e.g. as its table is created at runtime.
+    NoCatalog,
 }
 
 /// This struct represents a raw attribute which hasn't been mapped to a specific variant yet.
@@ -24,27 +100,205 @@ pub struct RawAttribute {
     pub name: Ident,
     /// The value of the attribute
     pub value: Option<String>,
+    /// The tokens within the parentheses of the attribute (if any), which retain their spans
+    pub tokens: Option<TokenStream>,
+}
+
+impl RawAttribute {
+    /// This method creates an error spanned on the value of the attribute (or on its name in
+    /// case it doesn't have a value).
+    pub fn error(&self, message: impl Display) -> syn::Error {
+        match &self.tokens {
+            Some(tokens) => syn::Error::new_spanned(tokens, message),
+            None => syn::Error::new(self.name.span(), message),
+        }
+    }
+
+    /// This method returns the value of the attribute, failing in case it doesn't have one.
+    fn required_value(&self) -> syn::Result<&str> {
+        self.value
+            .as_deref()
+            .ok_or_else(|| self.error(format!("`{}` requires a value", self.name)))
+    }
+}
+
+impl Attribute {
+    /// This function tries to map a given raw attribute to its corresponding attribute. In
+    /// case the name doesn't match any attribute, `None` is returned; in case the value isn't
+    /// valid, an error spanned on the attribute is returned.
+    pub fn parse(raw: RawAttribute) -> syn::Result<Option<Self>> {
+        let attribute = match raw.name.to_string().as_str() {
+            "rename" => Attribute::Rename {
+                new_name: parse_identifier(raw.required_value()?).map_err(|e| raw.error(e))?,
+            },
+            "p_key" => Attribute::PrimaryKey,
+            "tenant" => Attribute::Tenant,
+            "generated" => Attribute::Generated,
+            "flatten" => Attribute::Flatten {
+                prefix: raw
+                    .value
+                    .as_deref()
+                    .map(parse_identifier)
+                    .transpose()
+                    .map_err(|e| raw.error(e))?,
+            },
+            "sql_type" => Attribute::SqlType {
+                type_name: parse_type_name(raw.required_value()?).map_err(|e| raw.error(e))?,
+            },
+            "json" => Attribute::Json,
+            "encrypted" => Attribute::Encrypted,
+            "read_expr" => Attribute::ReadExpr {
+                expression: parse_expression(raw.required_value()?, false)
+                    .map_err(|e| raw.error(e))?,
+            },
+            "write_expr" => Attribute::WriteExpr {
+                expression: parse_expression(raw.required_value()?, true)
+                    .map_err(|e| raw.error(e))?,
+            },
+            "table" => Attribute::Table {
+                table_name: parse_table_name(raw.required_value()?).map_err(|e| raw.error(e))?,
+            },
+            "schema" => Attribute::Schema {
+                schema_name: parse_identifier(raw.required_value()?).map_err(|e| raw.error(e))?,
+            },
+            "ident" => {
+                raw.required_value()?;
+                Attribute::Identifier {
+                    ident_type: Box::new(parse_type(raw.tokens.clone().unwrap_or_default())?),
+                }
+            }
+            "describe" => Attribute::Describe {
+                options: parse_describe_options(raw.tokens.clone().unwrap_or_default())?,
+            },
+            _ => return Ok(None),
+        };
+
+        Ok(Some(attribute))
+    }
+}
+
+/// This function parses the comma separated options of the `describe` attribute, failing
+/// with an error spanned on the first invalid option.
+fn parse_describe_options(tokens: TokenStream) -> syn::Result<Vec<DescribeOption>> {
+    Punctuated::<Meta, Token![,]>::parse_terminated
+        .parse2(tokens)?
+        .into_iter()
+        .map(|meta| match meta {
+            Meta::Path(path) if path.is_ident("check") => Ok(DescribeOption::Check),
+            Meta::Path(path) if path.is_ident("from_row") => Ok(DescribeOption::FromRow),
+            Meta::Path(path) if path.is_ident("changeset") => Ok(DescribeOption::Changeset),
+            Meta::Path(path) if path.is_ident("new") => Ok(DescribeOption::New),
+            Meta::Path(path) if path.is_ident("no_catalog") => Ok(DescribeOption::NoCatalog),
+            Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(rule),
+                ..
+            }) if path.is_ident("rename_all") => rule
+                .value()
+                .parse()
+                .map(DescribeOption::RenameAll)
+                .map_err(|e| syn::Error::new_spanned(&rule, e)),
+            Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(dialect),
+                ..
+            }) if path.is_ident("dialect") => dialect
+                .value()
+                .parse()
+                .map(DescribeOption::Dialect)
+                .map_err(|e| syn::Error::new_spanned(&dialect, e)),
+            meta => Err(syn::Error::new_spanned(meta, "unknown describe option")),
+        })
+        .collect()
 }
 
-impl TryFrom<RawAttribute> for Attribute {
-    type Error = ();
-
-    /// This implementation tries to map a given raw attribute to its
-    /// corresponding attribute (if the name matches).
-    fn try_from(value: RawAttribute) -> Result<Self, Self::Error> {
-        match value.name.to_string().as_str() {
-            "rename" => Ok(Attribute::Rename {
-                new_name: value.value.unwrap(),
-            }),
-            "p_key" => Ok(Attribute::PrimaryKey),
-            "table" => Ok(Attribute::Table {
-                table_name: value.value.unwrap(),
-            }),
-            "ident" => Ok(Attribute::Identifier {
-                ident_type: syn::parse_str(&value.value.unwrap().replace("\"", ""))
-                    .expect("panicked here"),
-            }),
-            _ => Err(()),
+/// This function parses an identifier which is either given as a string literal or as a
+/// plain name, failing in case it isn't a legal identifier.
+fn parse_identifier(value: &str) -> Result<String, InvalidIdentifier> {
+    let identifier = match syn::parse_str::<LitStr>(value) {
+        Ok(literal) => literal.value(),
+        Err(_) => value.into(),
+    };
+
+    validate_identifier(&identifier)?;
+    Ok(identifier)
+}
+
+/// This function parses the name of a table just like an identifier, except that it may be
+/// qualified by its schema using a single dot (e.g. `my_schema.users`), in which case both
+/// parts have to be legal identifiers.
+fn parse_table_name(value: &str) -> Result<String, InvalidIdentifier> {
+    let table_name = match syn::parse_str::<LitStr>(value) {
+        Ok(literal) => literal.value(),
+        Err(_) => value.into(),
+    };
+
+    match table_name.split_once('.') {
+        Some((schema, table)) => validate_identifier(schema).and(validate_identifier(table)),
+        None => validate_identifier(&table_name),
+    }?;
+    Ok(table_name)
+}
+
+/// This function parses an SQL type which is given as a string literal, failing in case it
+/// isn't a legal type.
+fn parse_type_name(value: &str) -> Result<String, InvalidIdentifier> {
+    let type_name = syn::parse_str::<LitStr>(value)
+        .map(|literal| literal.value())
+        .unwrap_or_else(|_| value.into());
+
+    validate_type_name(&type_name)?;
+    Ok(type_name)
+}
+
+/// This function parses an SQL expression which is given as a string literal. A write
+/// expression has to contain the placeholder `{}` exactly once, as the value would be
+/// discarded or bound multiple times otherwise; a read expression may contain it at most once.
+fn parse_expression(value: &str, is_write: bool) -> Result<String, String> {
+    let expression = syn::parse_str::<LitStr>(value)
+        .map(|literal| literal.value())
+        .map_err(|_| format!("`{}` is not a valid expression, expected a string", value))?;
+
+    if expression.trim().is_empty() {
+        return Err("an expression must not be empty".into());
+    }
+    let placeholders = match render_expression(&expression, "") {
+        Some((_, placeholders)) => placeholders,
+        None => {
+            return Err(format!(
+                "the expression `{}` contains an unmatched brace, literal braces are written as `{{{{` and `}}}}`",
+                expression
+            ))
         }
+    };
+    if is_write && placeholders != 1 {
+        return Err(format!(
+            "the write expression `{}` must contain the placeholder `{{}}` exactly once",
+            expression
+        ));
+    }
+    if placeholders > 1 {
+        return Err(format!(
+            "the read expression `{}` must contain the placeholder `{{}}` at most once",
+            expression
+        ));
+    }
+
+    Ok(expression)
+}
+
+/// This function parses a type which is either given directly or as a string literal. The
+/// type retains the spans of the given tokens, so that errors regarding it are reported at
+/// the attribute.
+fn parse_type(tokens: TokenStream) -> syn::Result<Type> {
+    match syn::parse2::<LitStr>(tokens.clone()) {
+        Ok(literal) => literal.parse(),
+        Err(_) => syn::parse2(tokens.clone()),
     }
+    .map_err(|_| {
+        syn::Error::new_spanned(
+            &tokens,
+            format!("`{}` is not a valid identifier type", tokens),
+        )
+    })
 }
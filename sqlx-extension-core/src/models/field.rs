@@ -1,8 +1,8 @@
 use std::borrow::Cow;
 
-use syn::Ident;
+use syn::{Ident, Type};
 
-use crate::models::attribute::Attribute;
+use crate::models::{attribute::Attribute, rename_rule::RenameRule};
 
 /// This struct describes a single field of an
 /// entity.
@@ -11,6 +11,8 @@ pub struct Field {
     /// This is the identifier (i.e. the name)
     /// of an field.
     pub identifier: Ident,
+    /// This is the rust type of the field.
+    pub ty: Type,
     /// Those are all the attributes a given field has.
     pub attributes: Vec<Attribute>,
 }
@@ -18,7 +20,9 @@ pub struct Field {
 impl Field {
     /// This method tries to retrieve the "real" name of a field:
     /// If the field has a rename attribute, then the first encountered
-    /// version of it will be used, else the normal name is used.
+    /// version of it will be used, else the normal name is used (without
+    /// the `r#` prefix of raw identifiers).
+    /// The name is unquoted, i.e. exactly as it is stored within the database.
     pub fn name<'a>(&'a self) -> Cow<'a, str> {
         self.attributes
             .iter()
@@ -29,6 +33,85 @@ impl Field {
                     None
                 }
             })
-            .unwrap_or(Cow::Owned(self.identifier.to_string()))
+            .unwrap_or_else(|| Cow::Owned(self.raw_name()))
+    }
+
+    /// This method renames the field according to the given rule, unless it already has
+    /// an explicit rename attribute (which always takes precedence).
+    pub fn apply_rename_rule(&mut self, rule: RenameRule) {
+        let renamed = self
+            .attributes
+            .iter()
+            .any(|a| matches!(a, Attribute::Rename { .. }));
+        if !renamed {
+            let new_name = rule.apply(&self.raw_name());
+            self.attributes.push(Attribute::Rename { new_name });
+        }
+    }
+
+    /// This method returns the prefix of the field in case it is flattened (the prefix is
+    /// empty in case none was given).
+    pub fn flatten_prefix(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| {
+            if let Attribute::Flatten { prefix } = a {
+                Some(prefix.as_deref().unwrap_or_default())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// This method returns the SQL type the placeholders of the field are cast to (if any).
+    pub fn sql_type(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| {
+            if let Attribute::SqlType { type_name } = a {
+                Some(&type_name[..])
+            } else {
+                None
+            }
+        })
+    }
+
+    /// This method returns the expression the column of the field is read with (if any).
+    pub fn read_expr(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| {
+            if let Attribute::ReadExpr { expression } = a {
+                Some(&expression[..])
+            } else {
+                None
+            }
+        })
+    }
+
+    /// This method returns the expression the placeholders of the field are wrapped in (if
+    /// any).
+    pub fn write_expr(&self) -> Option<&str> {
+        self.attributes.iter().find_map(|a| {
+            if let Attribute::WriteExpr { expression } = a {
+                Some(&expression[..])
+            } else {
+                None
+            }
+        })
+    }
+
+    /// This method returns whether the field is stored as JSON.
+    pub fn is_json(&self) -> bool {
+        self.attributes.contains(&Attribute::Json)
+    }
+
+    /// This method returns whether the value of the field is generated by the database.
+    pub fn is_generated(&self) -> bool {
+        self.attributes.contains(&Attribute::Generated)
+    }
+
+    /// This method returns whether the field is encrypted at the application level.
+    pub fn is_encrypted(&self) -> bool {
+        self.attributes.contains(&Attribute::Encrypted)
+    }
+
+    /// This utility method returns the identifier of the field without the `r#` prefix.
+    fn raw_name(&self) -> String {
+        self.identifier.to_string().trim_start_matches("r#").into()
     }
 }
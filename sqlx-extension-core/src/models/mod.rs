@@ -1,2 +1,3 @@
 pub mod attribute;
 pub mod field;
+pub mod rename_rule;
@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase,
+    ToUpperCamelCase,
+};
+
+/// This enum represents a case-conversion strategy, which is applied to the names of all
+/// fields without an explicit `#[rename]` (as well as the name of the struct in case the
+/// table isn't given explicitly).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameRule {
+    /// Converts the name to `lowercase`.
+    LowerCase,
+    /// Converts the name to `UPPERCASE`.
+    UpperCase,
+    /// Converts the name to `PascalCase`.
+    PascalCase,
+    /// Converts the name to `camelCase`.
+    CamelCase,
+    /// Converts the name to `snake_case`.
+    SnakeCase,
+    /// Converts the name to `SCREAMING_SNAKE_CASE`.
+    ScreamingSnakeCase,
+    /// Converts the name to `kebab-case`.
+    KebabCase,
+    /// Converts the name to `SCREAMING-KEBAB-CASE`.
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    /// All supported rules alongside their names within the attribute.
+    const ALL: [(&'static str, RenameRule); 8] = [
+        ("lowercase", RenameRule::LowerCase),
+        ("UPPERCASE", RenameRule::UpperCase),
+        ("PascalCase", RenameRule::PascalCase),
+        ("camelCase", RenameRule::CamelCase),
+        ("snake_case", RenameRule::SnakeCase),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnakeCase),
+        ("kebab-case", RenameRule::KebabCase),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebabCase),
+    ];
+
+    /// This method converts the given name (either a field or a struct identifier)
+    /// according to the rule.
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::LowerCase => name.to_snake_case().replace('_', ""),
+            RenameRule::UpperCase => name.to_shouty_snake_case().replace('_', ""),
+            RenameRule::PascalCase => name.to_upper_camel_case(),
+            RenameRule::CamelCase => name.to_lower_camel_case(),
+            RenameRule::SnakeCase => name.to_snake_case(),
+            RenameRule::ScreamingSnakeCase => name.to_shouty_snake_case(),
+            RenameRule::KebabCase => name.to_kebab_case(),
+            RenameRule::ScreamingKebabCase => name.to_shouty_kebab_case(),
+        }
+    }
+}
+
+impl FromStr for RenameRule {
+    type Err = String;
+
+    /// This implementation parses a rule from its name, e.g. `camelCase`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, rule)| *rule)
+            .ok_or_else(|| {
+                let names = Self::ALL.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                format!(
+                    "`{}` is not a valid rename rule, expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_rules() {
+        let cases = [
+            (RenameRule::LowerCase, "createdat"),
+            (RenameRule::UpperCase, "CREATEDAT"),
+            (RenameRule::PascalCase, "CreatedAt"),
+            (RenameRule::CamelCase, "createdAt"),
+            (RenameRule::SnakeCase, "created_at"),
+            (RenameRule::ScreamingSnakeCase, "CREATED_AT"),
+            (RenameRule::KebabCase, "created-at"),
+            (RenameRule::ScreamingKebabCase, "CREATED-AT"),
+        ];
+        for (rule, expected) in cases.iter() {
+            assert_eq!(rule.apply("created_at"), *expected);
+        }
+        assert_eq!(RenameRule::SnakeCase.apply("UserAccount"), "user_account");
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!("camelCase".parse(), Ok(RenameRule::CamelCase));
+        assert_eq!(
+            "SCREAMING-KEBAB-CASE".parse(),
+            Ok(RenameRule::ScreamingKebabCase)
+        );
+        assert!("camelcase".parse::<RenameRule>().is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use sqlx::database::HasArguments;
+use sqlx::postgres::PgArguments;
+use sqlx::query::{Query, QueryAs};
+use sqlx::{Arguments, FromRow, Postgres, Result};
+
+use crate::maybe_transaction::MaybeTransaction;
+use crate::traits::{
+    bind_fields::BindFields, entity::Entity, postgres_dialect::PostgresDialect,
+    sql_describe::SqlDescribe, tenant_scoped::Unscoped,
+};
+
+/// This struct represents a single message stored within the outbox table.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct OutboxMessage {
+    /// The (generated) primary key of the message.
+    pub id: i64,
+    /// The topic the message should be delivered to.
+    pub topic: String,
+    /// The raw payload of the message.
+    pub payload: Vec<u8>,
+    /// Whether the message was already handed to a sink.
+    pub delivered: bool,
+}
+
+impl OutboxMessage {
+    /// This statement creates the outbox table in case it doesn't exist yet.
+    pub const CREATE_TABLE: &'static str = "CREATE TABLE IF NOT EXISTS outbox_messages (\
+        id BIGSERIAL PRIMARY KEY, \
+        topic TEXT NOT NULL, \
+        payload BYTEA NOT NULL, \
+        delivered BOOLEAN NOT NULL DEFAULT FALSE\
+    );";
+
+    /// This function enqueues a new message, returning its id.
+    /// The message is written using the transaction of the passed `MaybeTransaction`, so it
+    /// is only visible once the business write it belongs to has been committed.
+    pub async fn enqueue(
+        transaction: &mut MaybeTransaction<'_, Postgres>,
+        topic: &str,
+        payload: &[u8],
+    ) -> Result<i64> {
+        let (id,) = <Self as Entity>::insert_without_pk_return_pk()
+            .bind(topic)
+            .bind(payload)
+            .bind(false)
+            .fetch_one(transaction.get().await?)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// This function generates a query which claims at most `$1` undelivered messages,
+    /// skipping all rows which are currently locked by other pollers.
+    pub fn claim_undelivered<'q>(
+    ) -> QueryAs<'q, Postgres, Self, <Postgres as HasArguments<'q>>::Arguments> {
+        Self::custom_query_as_self(
+            "SELECT * FROM outbox_messages WHERE delivered = FALSE \
+             ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED;",
+        )
+    }
+
+    /// This function generates a query which marks all messages whose id is contained
+    /// within the array bound to `$1` as delivered.
+    pub fn mark_delivered<'q>() -> Query<'q, Postgres, <Postgres as HasArguments<'q>>::Arguments> {
+        sqlx::query("UPDATE outbox_messages SET delivered = TRUE WHERE id = ANY($1);")
+    }
+}
+
+impl SqlDescribe for OutboxMessage {
+    fn insert() -> &'static str {
+        "INSERT INTO outbox_messages (id, topic, payload, delivered) VALUES ($1, $2, $3, $4);"
+    }
+
+    fn insert_without_pk() -> &'static str {
+        "INSERT INTO outbox_messages (topic, payload, delivered) VALUES ($1, $2, $3) \
+         RETURNING id, topic, payload, delivered;"
+    }
+
+    fn insert_without_pk_return_pk() -> &'static str {
+        "INSERT INTO outbox_messages (topic, payload, delivered) VALUES ($1, $2, $3) RETURNING id;"
+    }
+
+    fn retrieve_all() -> &'static str {
+        "SELECT * FROM outbox_messages;"
+    }
+
+    fn retrieve_by_pk() -> &'static str {
+        "SELECT * FROM outbox_messages WHERE id = $1;"
+    }
+
+    fn retrieve_by_pk_for_update() -> &'static str {
+        "SELECT * FROM outbox_messages WHERE id = $1 FOR UPDATE;"
+    }
+
+    fn retrieve_by_pk_for_share() -> &'static str {
+        "SELECT * FROM outbox_messages WHERE id = $1 FOR SHARE;"
+    }
+
+    fn retrieve_by_pk_for_update_nowait() -> &'static str {
+        "SELECT * FROM outbox_messages WHERE id = $1 FOR UPDATE NOWAIT;"
+    }
+
+    fn retrieve_by_pk_for_update_skip_locked() -> &'static str {
+        "SELECT * FROM outbox_messages WHERE id = $1 FOR UPDATE SKIP LOCKED;"
+    }
+
+    fn update_by_pk() -> &'static str {
+        "UPDATE outbox_messages SET topic = $1, payload = $2, delivered = $3 WHERE id = $4;"
+    }
+
+    fn delete_by_pk() -> &'static str {
+        "DELETE FROM outbox_messages WHERE id = $1;"
+    }
+
+    fn table_name() -> &'static str {
+        "outbox_messages"
+    }
+}
+
+impl Unscoped for OutboxMessage {}
+
+impl PostgresDialect for OutboxMessage {}
+
+impl Entity for OutboxMessage {
+    type Identifier = (i64,);
+
+    fn identifier(&self) -> Self::Identifier {
+        (self.id,)
+    }
+}
+
+impl<'q> BindFields<'q, Postgres> for OutboxMessage {
+    fn bind_all(&'q self, arguments: &mut PgArguments) {
+        arguments.add(self.id);
+        self.bind_without_pk(arguments);
+    }
+
+    fn bind_without_pk(&'q self, arguments: &mut PgArguments) {
+        arguments.add(&self.topic);
+        arguments.add(&self.payload);
+        arguments.add(self.delivered);
+    }
+
+    fn bind_pk(&'q self, arguments: &mut PgArguments) {
+        arguments.add(self.id);
+    }
+}
@@ -0,0 +1,9 @@
+//! This module implements a transactional outbox: messages are written into the
+//! `outbox_messages` table within the same transaction as the business data and are
+//! later handed to a sink by the [`poller::OutboxPoller`].
+
+pub mod message;
+pub mod poller;
+
+pub use message::OutboxMessage;
+pub use poller::{OutboxError, OutboxPoller, OutboxSink};
@@ -0,0 +1,172 @@
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use futures_core::future::BoxFuture;
+use sqlx::error::BoxDynError;
+use sqlx::{Pool, Postgres};
+
+use super::message::OutboxMessage;
+use crate::maybe_transaction::MaybeTransaction;
+
+/// This trait describes the destination claimed outbox messages are handed to
+/// (e.g. a message broker).
+pub trait OutboxSink: Send + Sync {
+    /// This method delivers a single message. Returning an error stops the current
+    /// batch; the message (and all following ones) will be claimed again later on.
+    fn deliver<'a>(&'a self, message: &'a OutboxMessage) -> BoxFuture<'a, Result<(), BoxDynError>>;
+}
+
+/// This enum represents all errors which can occur while polling the outbox.
+#[derive(Debug)]
+pub enum OutboxError {
+    /// The database returned an error.
+    Database(sqlx::Error),
+    /// The sink failed to deliver the message with the given id.
+    Sink {
+        message_id: i64,
+        source: BoxDynError,
+    },
+}
+
+impl Display for OutboxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OutboxError::Database(e) => write!(f, "outbox database error: {}", e),
+            OutboxError::Sink { message_id, source } => {
+                write!(
+                    f,
+                    "failed to deliver outbox message {}: {}",
+                    message_id, source
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OutboxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutboxError::Database(e) => Some(e),
+            OutboxError::Sink { source, .. } => Some(&**source),
+        }
+    }
+}
+
+impl From<sqlx::Error> for OutboxError {
+    fn from(e: sqlx::Error) -> Self {
+        OutboxError::Database(e)
+    }
+}
+
+/// The callback `OutboxPoller::run` invokes for every failed poll.
+type ErrorHandler = Box<dyn Fn(&OutboxError) + Send + Sync>;
+
+/// This struct periodically claims undelivered messages, hands them to a sink
+/// and marks them as delivered afterwards.
+pub struct OutboxPoller<S> {
+    pool: Pool<Postgres>,
+    sink: S,
+    batch_size: i64,
+    interval: Duration,
+    on_error: Option<ErrorHandler>,
+}
+
+impl<S: OutboxSink> OutboxPoller<S> {
+    /// This function creates a new poller which claims up to 100 messages at once
+    /// and waits a second whenever the outbox is empty.
+    pub fn new(pool: Pool<Postgres>, sink: S) -> Self {
+        Self {
+            pool,
+            sink,
+            batch_size: 100,
+            interval: Duration::from_secs(1),
+            on_error: None,
+        }
+    }
+
+    /// This method sets the maximum number of messages claimed per batch.
+    pub fn batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// This method sets the time the poller waits in case the outbox was empty or a poll
+    /// failed.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// This method sets a callback which `run` invokes for every failed poll (e.g. to report
+    /// the error), before backing off and polling again.
+    pub fn on_error<F>(mut self, on_error: F) -> Self
+    where
+        F: Fn(&OutboxError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// This method claims a single batch of messages and hands them to the sink in order,
+    /// returning the number of delivered messages.
+    /// The rows are locked using `FOR UPDATE SKIP LOCKED`, so multiple pollers can work
+    /// on the same table concurrently.
+    pub async fn poll_once(&self) -> Result<usize, OutboxError> {
+        let mut transaction = MaybeTransaction::Pool(&self.pool);
+
+        let messages = OutboxMessage::claim_undelivered()
+            .bind(self.batch_size)
+            .fetch_all(transaction.get().await?)
+            .await?;
+
+        // deliver messages until the sink fails
+        let mut delivered = Vec::with_capacity(messages.len());
+        let mut failure = None;
+        for message in &messages {
+            match self.sink.deliver(message).await {
+                Ok(()) => delivered.push(message.id),
+                Err(source) => {
+                    failure = Some(OutboxError::Sink {
+                        message_id: message.id,
+                        source,
+                    });
+                    break;
+                }
+            }
+        }
+
+        if !delivered.is_empty() {
+            OutboxMessage::mark_delivered()
+                .bind(&delivered)
+                .execute(transaction.get().await?)
+                .await?;
+        }
+        transaction.commit().await?;
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(delivered.len()),
+        }
+    }
+
+    /// This method polls the outbox forever, sleeping for the configured interval
+    /// whenever no message was delivered. A failed poll doesn't stop the poller: the error is
+    /// handed to the `on_error` callback (and logged, if the `tracing` feature is enabled),
+    /// and the poller backs off for the configured interval before polling again.
+    pub async fn run(&self) {
+        loop {
+            match self.poll_once().await {
+                Ok(0) => tokio::time::sleep(self.interval).await,
+                Ok(_) => {}
+                Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!(error = %e, "failed to poll the outbox");
+                    if let Some(on_error) = &self.on_error {
+                        on_error(&e);
+                    }
+                    tokio::time::sleep(self.interval).await;
+                }
+            }
+        }
+    }
+}
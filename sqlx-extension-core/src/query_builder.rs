@@ -1,12 +1,115 @@
-use crate::models::field::Field;
+use crate::dialect::Dialect;
+use std::borrow::Cow;
 
-#[derive(Default)]
-pub struct QueryBuilder<'a> {
+use crate::models::{attribute::Attribute, field::Field};
+
+/// This enum represents the row-level lock a retrieve query acquires on all matching rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowLock {
+    /// `FOR UPDATE`: waits for other locks; blocks other transactions updating, deleting or
+    /// locking the rows using either `FOR UPDATE` or `FOR SHARE`.
+    ForUpdate,
+    /// `FOR SHARE`: waits for `FOR UPDATE` locks; blocks other transactions updating,
+    /// deleting or locking the rows using `FOR UPDATE`, but not the ones locking them using
+    /// `FOR SHARE` as well.
+    ForShare,
+    /// `FOR UPDATE NOWAIT`: fails immediately in case a row is already locked.
+    ForUpdateNowait,
+    /// `FOR UPDATE SKIP LOCKED`: silently skips all rows which are already locked.
+    ForUpdateSkipLocked,
+}
+
+impl RowLock {
+    /// This method returns the SQL clause of the lock.
+    pub fn clause(&self) -> &'static str {
+        match self {
+            RowLock::ForUpdate => "FOR UPDATE",
+            RowLock::ForShare => "FOR SHARE",
+            RowLock::ForUpdateNowait => "FOR UPDATE NOWAIT",
+            RowLock::ForUpdateSkipLocked => "FOR UPDATE SKIP LOCKED",
+        }
+    }
+}
+
+/// This trait describes a single column the query builder renders statements for. It is
+/// implemented by the fields parsed by the derive macros as well as by `StatementColumn`,
+/// which is used to render statements at runtime.
+pub trait QueryColumn {
+    /// This method returns the (unquoted) name of the column.
+    fn column_name(&self) -> Cow<'_, str>;
+
+    /// This method returns whether the column is part of the primary key.
+    fn is_primary_key(&self) -> bool;
+
+    /// This method returns whether the column contains the tenant.
+    fn is_tenant(&self) -> bool;
+
+    /// This method returns whether the value of the column is generated by the database.
+    fn is_generated(&self) -> bool;
+
+    /// This method returns the SQL type the placeholders of the column are cast to (if any).
+    fn sql_type(&self) -> Option<&str>;
+
+    /// This method returns the expression the column is read with (if any), in which `{}`
+    /// is replaced by the quoted column.
+    fn read_expr(&self) -> Option<&str>;
+
+    /// This method returns the expression the placeholders of the column are wrapped in (if
+    /// any), in which `{}` is replaced by the placeholder.
+    fn write_expr(&self) -> Option<&str>;
+}
+
+impl QueryColumn for Field {
+    fn column_name(&self) -> Cow<'_, str> {
+        self.name()
+    }
+
+    fn is_primary_key(&self) -> bool {
+        self.attributes.contains(&Attribute::PrimaryKey)
+    }
+
+    fn is_tenant(&self) -> bool {
+        self.attributes.contains(&Attribute::Tenant)
+    }
+
+    fn is_generated(&self) -> bool {
+        Field::is_generated(self)
+    }
+
+    fn sql_type(&self) -> Option<&str> {
+        Field::sql_type(self)
+    }
+
+    fn read_expr(&self) -> Option<&str> {
+        Field::read_expr(self)
+    }
+
+    fn write_expr(&self) -> Option<&str> {
+        Field::write_expr(self)
+    }
+}
+
+pub struct QueryBuilder<'a, C = Field> {
     table_name: Option<&'a str>,
-    entity_fields: Option<&'a [&'a Field]>,
+    schema_name: Option<&'a str>,
+    dialect: Dialect,
+    tenant_field: Option<&'a C>,
+    entity_fields: Option<&'a [&'a C]>,
 }
 
-impl<'a> QueryBuilder<'a> {
+impl<'a, C> Default for QueryBuilder<'a, C> {
+    fn default() -> Self {
+        Self {
+            table_name: None,
+            schema_name: None,
+            dialect: Dialect::default(),
+            tenant_field: None,
+            entity_fields: None,
+        }
+    }
+}
+
+impl<'a, C: QueryColumn> QueryBuilder<'a, C> {
     /// This function creates an empty query builder.
     pub fn new() -> Self {
         Self {
@@ -20,118 +123,440 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
+    /// This method sets the schema the table belongs to.
+    pub fn schema_name(mut self, name: &'a str) -> Self {
+        self.schema_name = Some(name);
+        self
+    }
+
+    /// This method sets the dialect used to quote identifiers and write placeholders.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// This method sets the field containing the tenant. All retrieve, update and delete
+    /// statements are restricted to the tenant bound to the first placeholder (`$1`), while
+    /// all other placeholders are shifted by one.
+    pub fn tenant_field(mut self, field: &'a C) -> Self {
+        self.tenant_field = Some(field);
+        self
+    }
+
+    /// This method returns the quoted name of the table, qualified by its schema (if any).
+    pub fn qualified_table_name(&self) -> Option<String> {
+        let table_name = self.dialect.quote_identifier(self.table_name?);
+        Some(match self.schema_name {
+            Some(schema_name) => {
+                format!(
+                    "{}.{}",
+                    self.dialect.quote_identifier(schema_name),
+                    table_name
+                )
+            }
+            None => table_name,
+        })
+    }
+
     /// This method sets the entity attributes for the query builder
-    pub fn entity_attributes(mut self, entity_fields: &'a [&'a Field]) -> Self {
+    pub fn entity_attributes(mut self, entity_fields: &'a [&'a C]) -> Self {
         self.entity_fields = Some(entity_fields);
         self
     }
 
     /// This method builds an insert statement.
     /// If the returns array is non-empty, those paramters will be added to the
-    /// `RETURNING` clause.
-    pub fn build_insert(&self, returns: &[&'a Field]) -> Option<String> {
-        let names = Self::fields_to_names(self.entity_fields?);
+    /// `RETURNING` clause. Without any entity attributes (e.g. when every column is a
+    /// generated primary key), the row is inserted with `DEFAULT VALUES`.
+    /// No query is built in case there is something to return, but the dialect doesn't
+    /// support `RETURNING`.
+    pub fn build_insert(&self, returns: &[&'a C]) -> Option<String> {
+        let fields = self.entity_fields?;
+        let names = self.fields_to_names(fields);
 
+        if !returns.is_empty() && !self.dialect.supports_returning() {
+            return None;
+        }
         let returns = if !returns.is_empty() {
-            let return_names = Self::fields_to_names(&returns);
-            format!("RETURNING {}", return_names)
+            format!("RETURNING {}", self.fields_to_select_list(returns))
         } else {
             "".into()
         };
 
+        if fields.is_empty() {
+            return Some(format!(
+                "INSERT INTO {} DEFAULT VALUES {};",
+                self.qualified_table_name()?,
+                returns,
+            ));
+        }
+
         Some(format!(
             "INSERT INTO {} ({}) VALUES ({}) {};",
-            self.table_name?,
+            self.qualified_table_name()?,
             names,
-            Self::get_n_placeholders(self.entity_fields?.len()),
+            self.get_placeholders(fields),
             returns,
         ))
     }
 
-    /// This method builds a simply retrieve query without a `WHERE` clause.
+    /// This method returns the list of columns selected by the retrieve queries: all columns
+    /// (`*`) unless one of the entity attributes is read with an expression, in which case
+    /// every column is listed explicitly.
+    pub fn select_list(&self) -> String {
+        match self.entity_fields {
+            Some(fields) if fields.iter().any(|f| f.read_expr().is_some()) => {
+                self.fields_to_select_list(fields)
+            }
+            _ => "*".into(),
+        }
+    }
+
+    /// This method builds a simply retrieve query without a `WHERE` clause (apart from the
+    /// one restricting the tenant).
     pub fn build_retrieve(&self) -> Option<String> {
-        Some(format!("SELECT * FROM {};", self.table_name?,))
+        Some(format!(
+            "SELECT {} FROM {}{};",
+            self.select_list(),
+            self.qualified_table_name()?,
+            self.tenant_condition()
+                .map(|c| format!(" WHERE {}", c))
+                .unwrap_or_default()
+        ))
     }
 
     /// This method builds a retrieve query with a `WHERE` clause which `AND`s all
     /// passed attributes.
-    pub fn build_retrieve_where(&self, where_fields: &[&Field]) -> Option<String> {
+    pub fn build_retrieve_where(&self, where_fields: &[&C]) -> Option<String> {
+        Some(format!(
+            "SELECT {} FROM {} {};",
+            self.select_list(),
+            self.qualified_table_name()?,
+            self.where_and_fields(where_fields)
+        ))
+    }
+
+    /// This method builds a retrieve query with a `WHERE` clause which `AND`s all
+    /// passed attributes, locking all matching rows using the given lock. No query is built
+    /// in case the dialect doesn't support row locks.
+    pub fn build_retrieve_where_locking(
+        &self,
+        where_fields: &[&C],
+        lock: RowLock,
+    ) -> Option<String> {
+        if !self.dialect.supports_row_locks() {
+            return None;
+        }
+
         Some(format!(
-            "SELECT * FROM {} {};",
-            self.table_name?,
-            Self::where_and_fields(where_fields)
+            "SELECT {} FROM {} {} {};",
+            self.select_list(),
+            self.qualified_table_name()?,
+            self.where_and_fields(where_fields),
+            lock.clause()
         ))
     }
 
     /// This method builds a delete query with a `WHERE` clause that ands all passed attributes.
-    pub fn build_delete_where(&self, where_fields: &[&Field]) -> Option<String> {
+    /// No query is built in case no attribute is passed, as it would delete the entire table.
+    pub fn build_delete_where(&self, where_fields: &[&C]) -> Option<String> {
+        if where_fields.is_empty() {
+            return None;
+        }
+
         Some(format!(
             "DELETE FROM {} {};",
-            self.table_name?,
-            Self::where_and_fields(where_fields)
+            self.qualified_table_name()?,
+            self.where_and_fields(where_fields)
         ))
     }
 
     /// This method builds an update query. The first array contains all fields which ought to
-    /// be set, the second one is used to construct a `WHERE` clause.
-    pub fn build_update(&self, set: &[Field], where_fields: &[&Field]) -> Option<String> {
-        // Make sure that the parameters to be set aren't empty
+    /// be set, the second one is used to construct a `WHERE` clause. The placeholders of the
+    /// `WHERE` clause continue after the ones of the `SET` part. No query is built in case
+    /// either array is empty, as there would be nothing to set or every row would be updated.
+    pub fn build_update(&self, set: &[&C], where_fields: &[&C]) -> Option<String> {
+        Some(format!("{};", self.update_clause(set, where_fields)?))
+    }
+
+    /// This method builds an update query just like `build_update`, which additionally
+    /// returns the given select list (e.g. `SqlDescribe::select_list`) of all updated rows.
+    /// No query is built in case the dialect doesn't support `RETURNING`.
+    pub fn build_update_returning(
+        &self,
+        set: &[&C],
+        where_fields: &[&C],
+        returning: &str,
+    ) -> Option<String> {
+        if !self.dialect.supports_returning() {
+            return None;
+        }
+
+        Some(format!(
+            "{} RETURNING {};",
+            self.update_clause(set, where_fields)?,
+            returning
+        ))
+    }
+
+    /// This method builds an update query without any `WHERE` clause (and without the
+    /// terminating semicolon), so that an arbitrary condition can be appended. The tenant (if
+    /// any) isn't restricted, hence the placeholders of the `SET` part start at `$1`.
+    pub fn build_update_without_where(&self, set: &[&C]) -> Option<String> {
         if set.is_empty() {
             return None;
         }
 
+        let set_par = self
+            .generate_equals_field(set.iter().copied(), 1, true)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Some(format!(
+            "UPDATE {} SET {}",
+            self.qualified_table_name()?,
+            set_par
+        ))
+    }
+
+    /// This method builds a `WHERE` clause comparing the given field to an array bound to
+    /// `$1` using `= ANY($1)`. As arrays can only be bound on Postgres, the dialect is ignored.
+    pub fn build_where_any(&self, field: &C) -> String {
+        let placeholder = match field.sql_type() {
+            Some(sql_type) => format!("$1::{}[]", sql_type),
+            None => "$1".into(),
+        };
+        format!(
+            "WHERE {} = ANY({})",
+            Dialect::Postgres.quote_identifier(&field.column_name()),
+            placeholder
+        )
+    }
+
+    /// This method builds a `WHERE` clause comparing the given fields to `count` values using
+    /// `IN`, numbering the placeholders starting at 1. In case of multiple fields (e.g. a
+    /// composite primary key), row values are compared, e.g. `("a", "b") IN (($1, $2))`.
+    pub fn build_where_in(&self, fields: &[&C], count: usize) -> String {
+        let names = self.fields_to_names(fields);
+        let mut index = 0;
+        let values = (0..count)
+            .map(|_| {
+                let placeholders = fields
+                    .iter()
+                    .map(|f| {
+                        index += 1;
+                        self.dialect.typed_placeholder(index, f.sql_type())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if fields.len() == 1 {
+                    placeholders
+                } else {
+                    format!("({})", placeholders)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if fields.len() == 1 {
+            format!("WHERE {} IN ({})", names, values)
+        } else {
+            format!("WHERE ({}) IN ({})", names, values)
+        }
+    }
+
+    /// This utility method builds the update query (without the terminating semicolon).
+    fn update_clause(&self, set: &[&C], where_fields: &[&C]) -> Option<String> {
+        // Make sure that neither the parameters to be set nor the conditions are empty
+        if set.is_empty() || where_fields.is_empty() {
+            return None;
+        }
+
         // generates the "SET par = $1" part
-        let set_par = Self::generate_equals_field(set.iter())
+        let first_index = self.first_placeholder();
+        let set_par = self
+            .generate_equals_field(set.iter().copied(), first_index, true)
             .collect::<Vec<_>>()
             .join(", ");
 
         // generates the filter part
-        let filter = Self::where_and_fields(where_fields);
+        let filter = self.where_and_fields_from(where_fields, first_index + set.len());
 
         Some(format!(
-            "UPDATE {} SET {} {};",
-            self.table_name?, set_par, filter
+            "UPDATE {} SET {} {}",
+            self.qualified_table_name()?,
+            set_par,
+            filter
         ))
     }
 
     /// This utility method generates a where clause in case the
     /// passed array is non-empty.
-    fn where_and_fields(where_fields: &[&Field]) -> String {
-        if where_fields.is_empty() {
+    fn where_and_fields(&self, where_fields: &[&C]) -> String {
+        self.where_and_fields_from(where_fields, self.first_placeholder())
+    }
+
+    /// This utility method generates a where clause in case the passed array is non-empty
+    /// (or a tenant field is set), numbering the placeholders starting at the given index.
+    fn where_and_fields_from(&self, where_fields: &[&C], first_index: usize) -> String {
+        let conditions = self
+            .generate_equals_field(where_fields.iter().copied(), first_index, false)
+            .chain(self.tenant_condition())
+            .collect::<Vec<_>>();
+
+        if conditions.is_empty() {
             "".into()
         } else {
-            let s = Self::generate_equals_field(where_fields.iter().map(|f| *f))
-                .collect::<Vec<_>>()
-                .join(" AND ");
-            format!("WHERE {}", s)
+            format!("WHERE {}", conditions.join(" AND "))
+        }
+    }
+
+    /// This utility method returns the condition restricting the tenant (if any).
+    fn tenant_condition(&self) -> Option<String> {
+        self.tenant_field.map(|f| {
+            format!(
+                "{} = {}",
+                self.dialect.quote_identifier(&f.column_name()),
+                self.dialect.typed_placeholder(1, f.sql_type())
+            )
+        })
+    }
+
+    /// This utility method returns the index of the first placeholder not used by the tenant.
+    fn first_placeholder(&self) -> usize {
+        if self.tenant_field.is_some() {
+            2
+        } else {
+            1
         }
     }
 
     /// This method converts the incoming interator and maps each item (=field) to the following:
-    /// `{FIELD} = ${INDEX}`;
+    /// `"{FIELD}" = ${INDEX}`, where the index starts at `first_index`; the placeholders are
+    /// wrapped in the write expressions of the fields if requested (i.e. within `SET`).
     fn generate_equals_field<'b>(
-        input: impl Iterator<Item = &'b Field> + 'b,
-    ) -> impl Iterator<Item = String> + 'b {
-        input
-            .enumerate()
-            .map(|(index, f)| format!("{} = ${}", f.name(), index + 1))
+        &self,
+        input: impl Iterator<Item = &'b C> + 'b,
+        first_index: usize,
+        write: bool,
+    ) -> impl Iterator<Item = String> + 'b
+    where
+        C: 'b,
+    {
+        let dialect = self.dialect;
+        input.enumerate().map(move |(index, f)| {
+            let placeholder = dialect.typed_placeholder(index + first_index, f.sql_type());
+            let value = match f.write_expr() {
+                Some(expression) if write => substitute(expression, &placeholder),
+                _ => placeholder,
+            };
+            format!("{} = {}", dialect.quote_identifier(&f.column_name()), value)
+        })
     }
 
-    /// This function maps an array of fields to their respective names.
-    fn fields_to_names(fields: &[&Field]) -> String {
+    /// This method maps an array of fields to their respective (quoted) names.
+    fn fields_to_names(&self, fields: &[&C]) -> String {
         fields
-            .into_iter()
-            .map(|f| f.name())
+            .iter()
+            .map(|f| self.dialect.quote_identifier(&f.column_name()))
             .collect::<Vec<_>>()
             .join(", ")
     }
 
-    /// This function generates n placeholders ala "$1, $2" etc.
-    fn get_n_placeholders(n: usize) -> String {
-        (1..n + 1)
-            .into_iter()
-            .map(|i| format!("${}", i))
+    /// This method maps an array of fields to the expressions they are selected with, i.e.
+    /// their (quoted) names or their read expressions aliased by their names.
+    fn fields_to_select_list(&self, fields: &[&C]) -> String {
+        fields
+            .iter()
+            .map(|f| {
+                let name = self.dialect.quote_identifier(&f.column_name());
+                match f.read_expr() {
+                    Some(expression) => {
+                        format!("{} AS {}", substitute(expression, &name), name)
+                    }
+                    None => name,
+                }
+            })
             .collect::<Vec<_>>()
             .join(", ")
     }
+
+    /// This method generates a placeholder for every field ala "$1, $2::my_enum" etc., each
+    /// wrapped in the write expression of the field (if any).
+    fn get_placeholders(&self, fields: &[&C]) -> String {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, f)| {
+                let placeholder = self.dialect.typed_placeholder(index + 1, f.sql_type());
+                match f.write_expr() {
+                    Some(expression) => substitute(expression, &placeholder),
+                    None => placeholder,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// This function substitutes the placeholder `{}` within the given read or write expression
+/// by the given value. Just like within `format!`, literal braces are written as `{{` and
+/// `}}`. It returns the result alongside the number of substituted placeholders, or `None` in
+/// case the expression contains an unmatched brace.
+pub fn render_expression(expression: &str, value: &str) -> Option<(String, usize)> {
+    let mut rendered = String::with_capacity(expression.len() + value.len());
+    let mut count = 0;
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                rendered.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                rendered.push_str(value);
+                count += 1;
+            }
+            ('{', _) | ('}', _) => return None,
+            _ => rendered.push(c),
+        }
+    }
+    Some((rendered, count))
+}
+
+/// This utility function substitutes the placeholder of the given expression, which has
+/// already been validated when parsing the attribute.
+fn substitute(expression: &str, value: &str) -> String {
+    render_expression(expression, value)
+        .map(|(rendered, _)| rendered)
+        .unwrap_or_else(|| expression.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_expressions() {
+        assert_eq!(
+            render_expression("lower({})", "$1"),
+            Some(("lower($1)".into(), 1))
+        );
+        assert_eq!(
+            render_expression("coalesce({}, '{{}}'::jsonb)", "$2"),
+            Some(("coalesce($2, '{}'::jsonb)".into(), 1))
+        );
+        assert_eq!(render_expression("now()", "$1"), Some(("now()".into(), 0)));
+        assert_eq!(
+            render_expression("{} || {}", "$1"),
+            Some(("$1 || $1".into(), 2))
+        );
+    }
+
+    #[test]
+    fn rejects_unmatched_braces() {
+        assert_eq!(render_expression("'{'", "$1"), None);
+        assert_eq!(render_expression("{} }", "$1"), None);
+    }
 }
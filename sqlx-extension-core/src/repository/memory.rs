@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+
+use futures_core::future::BoxFuture;
+use sqlx::error::DatabaseError;
+use sqlx::{Error, Result};
+
+use super::Repository;
+use crate::filter::Filter;
+use crate::traits::{entity::Entity, sql_describe::SqlDescribe, tenant_scoped::Unscoped};
+
+/// This struct implements a repository which keeps all entities in a `HashMap` keyed by
+/// their identifier. It is meant to be used in tests instead of a live database.
+/// Just like `PgRepository`, tenant-scoped entities aren't supported.
+pub struct InMemoryRepository<E: Entity> {
+    rows: Mutex<HashMap<E::Identifier, E>>,
+}
+
+impl<E> InMemoryRepository<E>
+where
+    E: Entity,
+    E::Identifier: Hash + Eq,
+{
+    /// This function creates an empty repository.
+    pub fn new() -> Self {
+        Self {
+            rows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// This function creates a repository already containing the given entities.
+    pub fn with_entities(entities: impl IntoIterator<Item = E>) -> Self {
+        Self {
+            rows: Mutex::new(entities.into_iter().map(|e| (e.identifier(), e)).collect()),
+        }
+    }
+
+    /// This method returns the number of stored entities.
+    pub fn len(&self) -> usize {
+        self.rows().len()
+    }
+
+    /// This method returns whether the repository is empty.
+    pub fn is_empty(&self) -> bool {
+        self.rows().is_empty()
+    }
+
+    /// This utility method locks the stored rows.
+    fn rows(&self) -> MutexGuard<'_, HashMap<E::Identifier, E>> {
+        self.rows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<E> Default for InMemoryRepository<E>
+where
+    E: Entity,
+    E::Identifier: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Repository<E> for InMemoryRepository<E>
+where
+    E: Entity + Unscoped + Clone + Send + Sync,
+    E::Identifier: Hash + Eq + Clone + Send + Sync,
+{
+    fn find<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<Option<E>>> {
+        let result = self.rows().get(id).cloned();
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn insert<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<()>> {
+        let result = match self.rows().entry(entity.identifier()) {
+            Entry::Occupied(_) => Err(Error::Database(Box::new(UniqueViolation {
+                table: <E as SqlDescribe>::table_name(),
+            }))),
+            Entry::Vacant(entry) => {
+                entry.insert(entity.clone());
+                Ok(())
+            }
+        };
+        Box::pin(async move { result })
+    }
+
+    fn update<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<bool>> {
+        let result = match self.rows().get_mut(&entity.identifier()) {
+            Some(stored) => {
+                *stored = entity.clone();
+                true
+            }
+            None => false,
+        };
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn delete<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<bool>> {
+        let result = self.rows().remove(id).is_some();
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn list<'a>(&'a self, filter: &'a Filter<E>) -> BoxFuture<'a, Result<Vec<E>>> {
+        let result = self
+            .rows()
+            .values()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect();
+        Box::pin(async move { Ok(result) })
+    }
+}
+
+/// This error is returned by the in-memory repository in case an entity with the same
+/// identifier already exists. It mimics the `unique_violation` error of Postgres.
+#[derive(Debug)]
+pub struct UniqueViolation {
+    table: &'static str,
+}
+
+impl Display for UniqueViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "duplicate key value violates the primary key of {}",
+            self.table
+        )
+    }
+}
+
+impl StdError for UniqueViolation {}
+
+impl DatabaseError for UniqueViolation {
+    fn message(&self) -> &str {
+        "duplicate key value violates unique constraint"
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed("23505"))
+    }
+
+    fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+        self
+    }
+}
@@ -0,0 +1,33 @@
+//! This module defines a repository abstraction over entities, allowing services to be
+//! tested without a live database.
+
+pub mod memory;
+pub mod postgres;
+
+use futures_core::future::BoxFuture;
+use sqlx::Result;
+
+use crate::filter::Filter;
+use crate::traits::entity::Entity;
+
+pub use memory::InMemoryRepository;
+pub use postgres::PgRepository;
+
+/// This trait describes a collection of entities which can be looked up by their identifier.
+pub trait Repository<E: Entity>: Send + Sync {
+    /// This method retrieves the entity with the given identifier (if any).
+    fn find<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<Option<E>>>;
+
+    /// This method inserts the given entity, including its primary key.
+    fn insert<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<()>>;
+
+    /// This method updates all fields of the stored entity sharing the identifier of the
+    /// given one. It returns whether such an entity existed.
+    fn update<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<bool>>;
+
+    /// This method deletes the entity with the given identifier, returning whether it existed.
+    fn delete<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<bool>>;
+
+    /// This method lists all entities matching the given filter.
+    fn list<'a>(&'a self, filter: &'a Filter<E>) -> BoxFuture<'a, Result<Vec<E>>>;
+}
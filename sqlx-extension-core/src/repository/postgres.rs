@@ -0,0 +1,120 @@
+use std::marker::PhantomData;
+
+use futures_core::future::BoxFuture;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::{query_as_with, query_with, FromRow, Pool, Postgres, Result};
+
+use super::Repository;
+use crate::cipher::ensure_field_cipher;
+use crate::filter::Filter;
+use crate::instrument::instrument;
+use crate::traits::{
+    bind_arguments::BindArguments, bind_fields::BindFields, entity::Entity,
+    postgres_dialect::PostgresDialect, sql_describe::SqlDescribe, tenant_scoped::Unscoped,
+};
+
+/// This struct implements a repository using the statements generated by `SqlDescribe`
+/// against a Postgres pool. Tenant-scoped entities aren't supported.
+pub struct PgRepository<E> {
+    pool: Pool<Postgres>,
+    entity: PhantomData<fn() -> E>,
+}
+
+impl<E> PgRepository<E> {
+    /// This function creates a new repository using the given pool.
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            entity: PhantomData,
+        }
+    }
+
+    /// This method returns the underlying pool.
+    pub fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+}
+
+impl<E> Clone for PgRepository<E> {
+    fn clone(&self) -> Self {
+        Self::new(self.pool.clone())
+    }
+}
+
+impl<E> Repository<E> for PgRepository<E>
+where
+    E: Entity
+        + Unscoped
+        + PostgresDialect
+        + Send
+        + Sync
+        + Unpin
+        + for<'r> FromRow<'r, PgRow>
+        + for<'q> BindFields<'q, Postgres>,
+    E::Identifier: Sync + for<'q> BindArguments<'q, Postgres>,
+{
+    fn find<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<Option<E>>> {
+        instrument::<E, _, _>("find", async move {
+            let mut arguments = PgArguments::default();
+            id.bind_arguments(&mut arguments);
+
+            query_as_with(<E as SqlDescribe>::retrieve_by_pk(), arguments)
+                .fetch_optional(&self.pool)
+                .await
+        })
+    }
+
+    fn insert<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<()>> {
+        instrument::<E, _, _>("insert", async move {
+            ensure_field_cipher::<E>()?;
+            let mut arguments = PgArguments::default();
+            entity.bind_all(&mut arguments);
+
+            query_with(<E as SqlDescribe>::insert(), arguments)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn update<'a>(&'a self, entity: &'a E) -> BoxFuture<'a, Result<bool>> {
+        instrument::<E, _, _>("update", async move {
+            ensure_field_cipher::<E>()?;
+            let mut arguments = PgArguments::default();
+            entity.bind_set(&mut arguments);
+            entity.bind_pk(&mut arguments);
+
+            let result = query_with(<E as SqlDescribe>::update_by_pk(), arguments)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn delete<'a>(&'a self, id: &'a E::Identifier) -> BoxFuture<'a, Result<bool>> {
+        instrument::<E, _, _>("delete", async move {
+            let mut arguments = PgArguments::default();
+            id.bind_arguments(&mut arguments);
+
+            let result = query_with(<E as SqlDescribe>::delete_by_pk(), arguments)
+                .execute(&self.pool)
+                .await?;
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn list<'a>(&'a self, filter: &'a Filter<E>) -> BoxFuture<'a, Result<Vec<E>>> {
+        instrument::<E, _, _>("list", async move {
+            let sql = format!(
+                "SELECT {} FROM {} {};",
+                <E as SqlDescribe>::select_list(),
+                <E as SqlDescribe>::table_name(),
+                filter.where_clause(1)
+            );
+            let mut arguments = PgArguments::default();
+            filter.bind(&mut arguments);
+
+            query_as_with(&sql, arguments).fetch_all(&self.pool).await
+        })
+    }
+}
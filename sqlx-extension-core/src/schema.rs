@@ -0,0 +1,111 @@
+//! This module contains a snapshot of the tables and columns of a database, which is used by
+//! the `Describe` derive macro to verify the generated statements at compile time.
+//!
+//! The derive macro fetches the schema from the database behind `DATABASE_URL`. In case the
+//! variable isn't set or `SQLX_OFFLINE` is enabled (e.g. in CI), it reads the file
+//! `sqlx-extension-schema.json` next to the manifest of the crate instead. This file is
+//! produced by calling `SchemaSnapshot::save` (e.g. from a test or a small binary run against
+//! the development database) and committed alongside the code.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+use sqlx::error::BoxDynError;
+use sqlx::{PgPool, Result};
+
+/// This struct contains the names of all columns of each table of a database.
+/// Tables within the search path are contained both with and without their schema
+/// (e.g. `users` and `public.users`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SchemaSnapshot {
+    tables: BTreeMap<String, Vec<String>>,
+}
+
+impl SchemaSnapshot {
+    /// The name of the file the snapshot is read from in offline mode, relative to the
+    /// directory of the manifest of the crate using the derive macro.
+    pub const FILE_NAME: &'static str = "sqlx-extension-schema.json";
+
+    /// This function retrieves the snapshot of the database behind the given pool.
+    pub async fn fetch(pool: &PgPool) -> Result<Self> {
+        let columns: Vec<(String, String, String, bool)> = sqlx::query_as(
+            "SELECT table_schema::TEXT, table_name::TEXT, column_name::TEXT, \
+             table_schema = ANY(current_schemas(false)) \
+             FROM information_schema.columns \
+             WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+             ORDER BY table_schema, table_name, ordinal_position;",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut snapshot = Self::default();
+        for (schema, table, column, in_search_path) in columns {
+            if in_search_path {
+                snapshot.add_column(&table, &column);
+            }
+            snapshot.add_column(&format!("{}.{}", schema, table), &column);
+        }
+
+        Ok(snapshot)
+    }
+
+    /// This function parses a snapshot from its JSON representation, i.e. an object mapping
+    /// each table to an array of its columns.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let tables: BTreeMap<String, Vec<String>> = serde_json::from_str(json)?;
+        Ok(Self { tables })
+    }
+
+    /// This method converts the snapshot into its JSON representation.
+    pub fn to_json(&self) -> String {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(table, columns)| {
+                let columns = columns.iter().cloned().map(Value::String).collect();
+                (table.clone(), Value::Array(columns))
+            })
+            .collect::<Map<_, _>>();
+
+        serde_json::to_string_pretty(&Value::Object(tables)).unwrap_or_default()
+    }
+
+    /// This function reads a snapshot from the given file.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// This method writes the snapshot to the given file, so it can be used offline.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    /// This function fetches the snapshot of the database behind the given pool and writes it
+    /// to `FILE_NAME` within the given directory, which has to be the one containing the
+    /// manifest of the crate using the derive macro (i.e. `env!("CARGO_MANIFEST_DIR")`).
+    pub async fn save(
+        pool: &PgPool,
+        directory: impl AsRef<Path>,
+    ) -> std::result::Result<(), BoxDynError> {
+        let snapshot = Self::fetch(pool).await?;
+        snapshot.write(directory.as_ref().join(Self::FILE_NAME))?;
+        Ok(())
+    }
+
+    /// This method adds a column to the given table.
+    pub fn add_column(&mut self, table: &str, column: &str) {
+        self.tables
+            .entry(table.into())
+            .or_default()
+            .push(column.into());
+    }
+
+    /// This method returns all columns of the given table (if it exists).
+    pub fn columns(&self, table: &str) -> Option<&[String]> {
+        self.tables.get(table).map(|c| &c[..])
+    }
+}
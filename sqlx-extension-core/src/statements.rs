@@ -0,0 +1,261 @@
+//! This module renders all statements of an entity at once. The `Describe` derive macro
+//! uses it at compile time, while entities with flattened fields (whose columns are only
+//! known once all types are compiled) render their statements at runtime.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use once_cell::sync::{Lazy, OnceCell};
+
+use crate::query_builder::{QueryBuilder, QueryColumn, RowLock};
+
+/// All statements interned so far, see `intern`.
+static INTERNED: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// This function interns the given statement rendered at runtime, so it can be used just
+/// like the generated ones. Every distinct statement is only leaked once, hence it must only
+/// be used for statements of which there is a bounded number (e.g. one per combination of
+/// the fields of an entity).
+pub fn intern(statement: String) -> &'static str {
+    let cached = INTERNED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&statement[..])
+        .copied();
+    if let Some(statement) = cached {
+        return statement;
+    }
+
+    let mut interned = INTERNED.write().unwrap_or_else(|e| e.into_inner());
+    match interned.get(&statement[..]) {
+        Some(statement) => statement,
+        None => {
+            let statement: &'static str = Box::leak(statement.into_boxed_str());
+            interned.insert(statement);
+            statement
+        }
+    }
+}
+
+/// This struct contains every statement described by `SqlDescribe`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Statements {
+    /// The statement returned by `SqlDescribe::insert`.
+    pub insert: String,
+    /// The statement returned by `SqlDescribe::insert_without_pk`.
+    pub insert_without_pk: String,
+    /// The statement returned by `SqlDescribe::insert_without_pk_return_pk`.
+    pub insert_without_pk_return_pk: String,
+    /// The statement returned by `SqlDescribe::retrieve_all`.
+    pub retrieve_all: String,
+    /// The statement returned by `SqlDescribe::retrieve_by_pk`.
+    pub retrieve_by_pk: String,
+    /// The statement returned by `SqlDescribe::retrieve_by_pk_for_update`.
+    pub retrieve_by_pk_for_update: String,
+    /// The statement returned by `SqlDescribe::retrieve_by_pk_for_share`.
+    pub retrieve_by_pk_for_share: String,
+    /// The statement returned by `SqlDescribe::retrieve_by_pk_for_update_nowait`.
+    pub retrieve_by_pk_for_update_nowait: String,
+    /// The statement returned by `SqlDescribe::retrieve_by_pk_for_update_skip_locked`.
+    pub retrieve_by_pk_for_update_skip_locked: String,
+    /// The statement returned by `SqlDescribe::update_by_pk`.
+    pub update_by_pk: String,
+    /// The statement returned by `SqlDescribe::delete_by_pk`.
+    pub delete_by_pk: String,
+    /// The select list returned by `SqlDescribe::select_list`.
+    pub select_list: String,
+}
+
+impl Statements {
+    /// This function renders all statements for the given columns, using the given builder
+    /// (which already knows the table).
+    pub fn render<'a, C: QueryColumn>(
+        builder: QueryBuilder<'a, C>,
+        all_columns: &'a [&'a C],
+    ) -> Self {
+        // split the columns into contained / not contained in primary key
+        let (columns_in_pk, columns_not_in_pk): (Vec<_>, Vec<_>) = all_columns
+            .iter()
+            .copied()
+            .partition(|c| c.is_primary_key());
+
+        // the generated columns are neither inserted (apart from `insert`) nor updated
+        let columns_not_in_pk = columns_not_in_pk
+            .into_iter()
+            .filter(|c| !c.is_generated())
+            .collect::<Vec<_>>();
+
+        // the tenant is restricted by the builder itself, so it is neither part of the `SET`
+        // nor of the primary key conditions
+        let pk_conditions = columns_in_pk
+            .iter()
+            .copied()
+            .filter(|c| !c.is_tenant())
+            .collect::<Vec<_>>();
+        let set_columns = columns_not_in_pk
+            .iter()
+            .copied()
+            .filter(|c| !c.is_tenant())
+            .collect::<Vec<_>>();
+
+        // the retrieve statements select all columns of the entity
+        let builder = builder.entity_attributes(all_columns);
+        let insert = builder.build_insert(&[]).unwrap_or_default();
+        let retrieve_all = builder.build_retrieve().unwrap_or_default();
+        let retrieve_by_pk = builder
+            .build_retrieve_where(&pk_conditions)
+            .unwrap_or_default();
+        let retrieve_locking = |lock| {
+            builder
+                .build_retrieve_where_locking(&pk_conditions, lock)
+                .unwrap_or_default()
+        };
+        let retrieve_by_pk_for_update = retrieve_locking(RowLock::ForUpdate);
+        let retrieve_by_pk_for_share = retrieve_locking(RowLock::ForShare);
+        let retrieve_by_pk_for_update_nowait = retrieve_locking(RowLock::ForUpdateNowait);
+        let retrieve_by_pk_for_update_skip_locked = retrieve_locking(RowLock::ForUpdateSkipLocked);
+        let select_list = builder.select_list();
+
+        // make sure to change entity attributes to be just the ones which
+        // are not in the PK
+        let builder = builder.entity_attributes(&columns_not_in_pk);
+        let insert_without_pk = builder.build_insert(all_columns).unwrap_or_default();
+        let insert_without_pk_return_pk = builder.build_insert(&columns_in_pk).unwrap_or_default();
+
+        Self {
+            insert,
+            insert_without_pk,
+            insert_without_pk_return_pk,
+            retrieve_all,
+            retrieve_by_pk,
+            retrieve_by_pk_for_update,
+            retrieve_by_pk_for_share,
+            retrieve_by_pk_for_update_nowait,
+            retrieve_by_pk_for_update_skip_locked,
+            update_by_pk: builder
+                .build_update(&set_columns, &pk_conditions)
+                .unwrap_or_default(),
+            delete_by_pk: builder
+                .build_delete_where(&pk_conditions)
+                .unwrap_or_default(),
+            select_list,
+        }
+    }
+
+    /// This method returns all statements alongside the name of their `SqlDescribe` function
+    /// (the select list isn't a statement on its own, so it isn't contained).
+    pub fn named(&self) -> [(&'static str, &str); 11] {
+        [
+            ("insert", &self.insert),
+            ("insert_without_pk", &self.insert_without_pk),
+            (
+                "insert_without_pk_return_pk",
+                &self.insert_without_pk_return_pk,
+            ),
+            ("retrieve_all", &self.retrieve_all),
+            ("retrieve_by_pk", &self.retrieve_by_pk),
+            ("retrieve_by_pk_for_update", &self.retrieve_by_pk_for_update),
+            ("retrieve_by_pk_for_share", &self.retrieve_by_pk_for_share),
+            (
+                "retrieve_by_pk_for_update_nowait",
+                &self.retrieve_by_pk_for_update_nowait,
+            ),
+            (
+                "retrieve_by_pk_for_update_skip_locked",
+                &self.retrieve_by_pk_for_update_skip_locked,
+            ),
+            ("update_by_pk", &self.update_by_pk),
+            ("delete_by_pk", &self.delete_by_pk),
+        ]
+    }
+}
+
+/// This struct holds the statements of an entity which are rendered at runtime. The
+/// `Describe` derive macro declares a static cell for every entity with flattened fields, so
+/// their statements are rendered once and there is a single copy per entity (which lives as
+/// long as the program, so it can be returned as `&'static str` just like the generated ones).
+#[derive(Debug, Default)]
+pub struct StatementsCell {
+    statements: OnceCell<Statements>,
+}
+
+impl StatementsCell {
+    /// This function creates an empty cell.
+    pub const fn new() -> Self {
+        Self {
+            statements: OnceCell::new(),
+        }
+    }
+
+    /// This method returns the statements within the cell, rendering them using the given
+    /// function on first use.
+    pub fn get_or_render(&self, render: impl FnOnce() -> Statements) -> &Statements {
+        self.statements.get_or_init(render)
+    }
+}
+
+/// This struct describes a single column whose statements are rendered at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementColumn {
+    /// The (unquoted) name of the column.
+    pub name: String,
+    /// Whether the column is part of the primary key.
+    pub primary_key: bool,
+    /// Whether the column contains the tenant.
+    pub tenant: bool,
+    /// Whether the value of the column is generated by the database.
+    pub generated: bool,
+    /// The SQL type the placeholders of the column are cast to (if any).
+    pub sql_type: Option<String>,
+    /// The expression the column is read with (if any).
+    pub read_expr: Option<String>,
+    /// The expression the placeholders of the column are wrapped in (if any).
+    pub write_expr: Option<String>,
+}
+
+impl StatementColumn {
+    /// This function creates a column which is neither part of the primary key nor contains
+    /// the tenant.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            primary_key: false,
+            tenant: false,
+            generated: false,
+            sql_type: None,
+            read_expr: None,
+            write_expr: None,
+        }
+    }
+}
+
+impl QueryColumn for StatementColumn {
+    fn column_name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    fn is_tenant(&self) -> bool {
+        self.tenant
+    }
+
+    fn is_generated(&self) -> bool {
+        self.generated
+    }
+
+    fn sql_type(&self) -> Option<&str> {
+        self.sql_type.as_deref()
+    }
+
+    fn read_expr(&self) -> Option<&str> {
+        self.read_expr.as_deref()
+    }
+
+    fn write_expr(&self) -> Option<&str> {
+        self.write_expr.as_deref()
+    }
+}
@@ -0,0 +1,118 @@
+//! This module allows rendering the generated statements against a schema which is only
+//! known at runtime, e.g. in case every tenant of a Postgres database has its own schema.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::dialect::{validate_identifier, InvalidIdentifier};
+use crate::statements::Statements;
+use crate::traits::render_statements::RenderStatements;
+use crate::traits::sql_describe::SqlDescribe;
+
+/// The maximum number of entity and tenant combinations whose statements are cached. Once
+/// the cache is full, it is cleared, so the statements are rendered again on demand.
+const MAX_CACHED_STATEMENTS: usize = 1024;
+
+/// All statements rendered so far, keyed by the entity and the tenant schema.
+static RENDERED: Lazy<RwLock<RenderedStatements>> = Lazy::new(Default::default);
+
+/// The rendered statements, keyed by the type of the entity and the name of the schema.
+type RenderedStatements = HashMap<(TypeId, String), Arc<Statements>>;
+
+/// This struct represents the (validated) schema of a single tenant.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TenantSchema {
+    name: String,
+}
+
+impl TenantSchema {
+    /// This function creates a tenant schema, failing in case the name isn't a legal
+    /// identifier.
+    pub fn new(name: impl Into<String>) -> Result<Self, InvalidIdentifier> {
+        let name = name.into();
+        validate_identifier(&name)?;
+        Ok(Self { name })
+    }
+
+    /// This method returns the (unquoted) name of the schema.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// This method returns the quoted name of the table of the given entity, qualified by
+    /// the schema of the tenant.
+    pub fn table_name<E: SqlDescribe + ?Sized>(&self) -> String {
+        format!(
+            "{}.{}",
+            E::dialect().quote_identifier(&self.name),
+            E::table_name_without_schema()
+        )
+    }
+
+    /// This method returns the statements of the given entity rendered against the schema of
+    /// the tenant (replacing a schema given at compile time). The statements are cached per
+    /// entity and tenant, so they are usually only rendered once.
+    pub fn statements<E: RenderStatements + 'static>(&self) -> TenantStatements<E> {
+        let key = (TypeId::of::<E>(), self.name.clone());
+        let cached = RENDERED
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .cloned();
+
+        let statements = match cached {
+            Some(statements) => statements,
+            None => {
+                let statements = Arc::new(E::render_statements(Some(&self.name)));
+                let mut rendered = RENDERED.write().unwrap_or_else(|e| e.into_inner());
+                if rendered.len() >= MAX_CACHED_STATEMENTS {
+                    rendered.clear();
+                }
+                rendered.entry(key).or_insert(statements).clone()
+            }
+        };
+
+        TenantStatements {
+            statements,
+            entity: PhantomData,
+        }
+    }
+}
+
+/// This struct contains the statements of an entity rendered against the schema of a single
+/// tenant, which are passed to the functions of `TenantEntity`.
+pub struct TenantStatements<E: ?Sized> {
+    statements: Arc<Statements>,
+    entity: PhantomData<fn() -> E>,
+}
+
+impl<E: ?Sized> Deref for TenantStatements<E> {
+    type Target = Statements;
+
+    fn deref(&self) -> &Self::Target {
+        &self.statements
+    }
+}
+
+impl<E: ?Sized> Clone for TenantStatements<E> {
+    fn clone(&self) -> Self {
+        Self {
+            statements: self.statements.clone(),
+            entity: PhantomData,
+        }
+    }
+}
+
+impl<E: ?Sized> Debug for TenantStatements<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TenantStatements")
+            .field(&self.statements)
+            .finish()
+    }
+}
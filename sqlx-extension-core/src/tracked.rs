@@ -0,0 +1,133 @@
+//! This module allows tracking the changes of a loaded entity, so only the columns which
+//! actually changed are updated when it is saved.
+
+use std::fmt::{self, Debug, Formatter};
+use std::ops::Deref;
+
+use futures_core::future::BoxFuture;
+use sqlx::database::HasArguments;
+use sqlx::{Database, Error, Executor, FromRow, IntoArguments, Result, Row};
+
+use crate::traits::bind_arguments::BindArguments;
+use crate::traits::changeset::{BindChangeset, Changeset};
+use crate::traits::entity::Entity;
+use crate::traits::tenant_scoped::Unscoped;
+use crate::traits::track_changes::{TrackChanges, TrackedField};
+
+/// This struct wraps an entity alongside a flag for every field of its changeset, which is
+/// set whenever the field is changed using `set` or `modify`. It dereferences to the entity,
+/// but only immutably, so every change is tracked.
+/// It can be decoded from a row, e.g. `User::retrieve_by_pk::<_, Tracked<User>>()`.
+#[derive(Clone)]
+pub struct Tracked<E> {
+    entity: E,
+    dirty: Vec<bool>,
+}
+
+impl<E: TrackChanges> Tracked<E> {
+    /// This function starts tracking the changes of the given (already stored) entity.
+    pub fn new(entity: E) -> Self {
+        Self {
+            entity,
+            dirty: vec![false; E::TRACKED_FIELDS.len()],
+        }
+    }
+
+    /// This method replaces the value of the given field, marking it as changed.
+    pub fn set<T>(&mut self, field: TrackedField<E, T>, value: T) {
+        *field.get_mut(&mut self.entity) = value;
+        self.dirty[field.index()] = true;
+    }
+
+    /// This method modifies the value of the given field in place, marking it as changed.
+    pub fn modify<T>(&mut self, field: TrackedField<E, T>, modify: impl FnOnce(&mut T)) {
+        modify(field.get_mut(&mut self.entity));
+        self.dirty[field.index()] = true;
+    }
+
+    /// This method returns whether any field has been changed.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.contains(&true)
+    }
+
+    /// This method returns the names of all fields which have been changed.
+    pub fn dirty_fields(&self) -> Vec<&'static str> {
+        E::TRACKED_FIELDS
+            .iter()
+            .zip(&self.dirty)
+            .filter(|(_, dirty)| **dirty)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// This method returns a changeset containing all fields which have been changed.
+    pub fn changeset(&self) -> E::Changeset {
+        self.entity.changes(&self.dirty)
+    }
+
+    /// This method updates all fields which have been changed (and nothing at all in case
+    /// no field has been changed). Afterwards, the entity is replaced by the updated row and
+    /// no field is marked as changed anymore.
+    /// In case the entity doesn't exist anymore, `Error::RowNotFound` is returned.
+    pub fn save<'a, 'c, DB, X>(&'a mut self, executor: X) -> BoxFuture<'a, Result<()>>
+    where
+        'c: 'a,
+        DB: Database,
+        X: 'a + Executor<'c, Database = DB>,
+        E: Entity + Unscoped + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+        E::Changeset: Send + Sync + for<'q> BindChangeset<'q, DB>,
+        E::Identifier: Send + Sync + for<'q> BindArguments<'q, DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    {
+        Box::pin(async move {
+            let changeset = self.changeset();
+            if changeset.update_by_pk_returning().is_none() {
+                return Ok(());
+            }
+
+            let pk = self.entity.identifier();
+            let updated = changeset
+                .apply(&pk, executor)
+                .await?
+                .ok_or(Error::RowNotFound)?;
+
+            self.entity = updated;
+            self.dirty.iter_mut().for_each(|dirty| *dirty = false);
+            Ok(())
+        })
+    }
+}
+
+impl<E> Tracked<E> {
+    /// This method stops tracking the changes, returning the (modified) entity.
+    pub fn into_inner(self) -> E {
+        self.entity
+    }
+}
+
+impl<E> Deref for Tracked<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entity
+    }
+}
+
+impl<E: TrackChanges + Debug> Debug for Tracked<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracked")
+            .field("entity", &self.entity)
+            .field("dirty_fields", &self.dirty_fields())
+            .finish()
+    }
+}
+
+impl<'r, R, E> FromRow<'r, R> for Tracked<E>
+where
+    R: Row,
+    E: FromRow<'r, R> + TrackChanges,
+{
+    fn from_row(row: &'r R) -> Result<Self> {
+        E::from_row(row).map(Self::new)
+    }
+}
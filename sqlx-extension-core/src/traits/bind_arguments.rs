@@ -0,0 +1,30 @@
+use sqlx::database::HasArguments;
+use sqlx::{Arguments, Database, Encode, Type};
+
+/// This trait binds a value consisting of one or multiple columns (e.g. the identifier of an
+/// entity) to the arguments of a statement.
+pub trait BindArguments<'q, DB: Database> {
+    /// This method adds all contained values to the given arguments, in order.
+    fn bind_arguments(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+}
+
+/// This macro implements `BindArguments` for a tuple of the given arity.
+macro_rules! impl_bind_arguments_for_tuple {
+    ($($index:tt => $name:ident),+) => {
+        impl<'q, DB: Database, $($name),+> BindArguments<'q, DB> for ($($name,)+)
+        where
+            $($name: 'q + Sync + Encode<'q, DB> + Type<DB>),+
+        {
+            fn bind_arguments(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments) {
+                $(arguments.add(&self.$index);)+
+            }
+        }
+    };
+}
+
+impl_bind_arguments_for_tuple!(0 => T1);
+impl_bind_arguments_for_tuple!(0 => T1, 1 => T2);
+impl_bind_arguments_for_tuple!(0 => T1, 1 => T2, 2 => T3);
+impl_bind_arguments_for_tuple!(0 => T1, 1 => T2, 2 => T3, 3 => T4);
+impl_bind_arguments_for_tuple!(0 => T1, 1 => T2, 2 => T3, 3 => T4, 4 => T5);
+impl_bind_arguments_for_tuple!(0 => T1, 1 => T2, 2 => T3, 3 => T4, 4 => T5, 5 => T6);
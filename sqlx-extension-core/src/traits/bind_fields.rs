@@ -0,0 +1,26 @@
+use sqlx::database::HasArguments;
+use sqlx::Database;
+
+/// This trait binds the fields of an entity to the arguments of the statements generated
+/// by `SqlDescribe`. It should almost never be implemented directly, but rather using the
+/// `Describe` derive macro.
+pub trait BindFields<'q, DB: Database> {
+    /// This method binds all fields in the order expected by `SqlDescribe::insert`.
+    fn bind_all(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+
+    /// This method binds all fields which are neither annotated with `p_key` nor `generated`,
+    /// in the order expected by `SqlDescribe::insert_without_pk`.
+    fn bind_without_pk(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+
+    /// This method binds all fields annotated with `p_key` (apart from the tenant), in the
+    /// order expected by `SqlDescribe::retrieve_by_pk` and `SqlDescribe::delete_by_pk`.
+    fn bind_pk(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+
+    /// This method binds all fields which are neither annotated with `p_key`, `generated` nor
+    /// `tenant`, in the order expected by the `SET` part of `SqlDescribe::update_by_pk`. By
+    /// default, it binds the same fields as `bind_without_pk`, which only differ in case of a
+    /// tenant.
+    fn bind_set(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments) {
+        self.bind_without_pk(arguments);
+    }
+}
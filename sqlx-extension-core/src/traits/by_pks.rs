@@ -0,0 +1,138 @@
+use futures_core::future::BoxFuture;
+use sqlx::database::HasArguments;
+use sqlx::{
+    query_as_with, query_with, Acquire, Database, Executor, FromRow, IntoArguments, Result,
+};
+
+use super::entity::Entity;
+use super::sql_describe::SqlDescribe;
+use super::tenant_scoped::Unscoped;
+use crate::instrument::instrument;
+
+/// This trait retrieves and deletes entities by a list of identifiers. It is implemented by
+/// the `Entity` derive macro for every entity without a `tenant` field: on Postgres, a primary
+/// key consisting of a single field of a common type (e.g. `i64`, `String` or `Uuid`) is
+/// compared using `= ANY($1)`, binding all identifiers as a single array. Otherwise, the
+/// identifiers are compared using `IN` (comparing row values in case of a composite primary
+/// key), split into chunks which don't exceed the maximum number of placeholders of the
+/// dialect.
+pub trait ByPks<DB: Database>: Entity + Unscoped {
+    /// This function returns the maximum number of identifiers compared by a single statement.
+    fn chunk_size() -> usize;
+
+    /// This function returns the `WHERE` clause comparing the primary key to the given number
+    /// of identifiers, whose placeholders start at 1.
+    fn by_pks_condition(count: usize) -> String;
+
+    /// This function binds the given identifiers, in the order expected by `by_pks_condition`.
+    fn bind_pks<'q>(
+        pks: &'q [Self::Identifier],
+        arguments: &mut <DB as HasArguments<'q>>::Arguments,
+    );
+
+    /// This function retrieves all entities with one of the given identifiers; identifiers
+    /// without an entity are skipped. The entities aren't returned in any particular order.
+    /// The chunks are retrieved one after another outside of a transaction, so they might
+    /// observe different states of the table unless `acquire` is a transaction itself.
+    fn retrieve_by_pks<'a, 'c, A>(
+        pks: &'a [Self::Identifier],
+        acquire: A,
+    ) -> BoxFuture<'a, Result<Vec<Self>>>
+    where
+        'c: 'a,
+        A: 'a + Send + Acquire<'c, Database = DB>,
+        Self: Sized + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+        Self::Identifier: Sync,
+        for<'e> &'e mut DB::Connection: Executor<'e, Database = DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    {
+        instrument::<Self, _, _>("retrieve_by_pks", async move {
+            let mut entities = Vec::with_capacity(pks.len());
+            if pks.is_empty() {
+                return Ok(entities);
+            }
+
+            let mut connection = acquire.acquire().await?;
+            for chunk in pks.chunks(Self::chunk_size()) {
+                let sql = format!(
+                    "SELECT {} FROM {} {};",
+                    <Self as SqlDescribe>::select_list(),
+                    <Self as SqlDescribe>::table_name(),
+                    Self::by_pks_condition(chunk.len())
+                );
+                let mut arguments = <DB as HasArguments<'_>>::Arguments::default();
+                Self::bind_pks(chunk, &mut arguments);
+
+                entities.extend(
+                    query_as_with(&sql, arguments)
+                        .fetch_all(&mut *connection)
+                        .await?,
+                );
+            }
+            Ok(entities)
+        })
+    }
+
+    /// This function deletes all entities with one of the given identifiers, returning the
+    /// combined result of all executed statements (e.g. to retrieve the number of deleted
+    /// rows). In case the identifiers are split into multiple chunks, they are deleted within
+    /// a transaction (a savepoint in case `acquire` is a transaction already), so either all
+    /// or none of the entities are deleted.
+    fn delete_by_pks<'a, 'c, A>(
+        pks: &'a [Self::Identifier],
+        acquire: A,
+    ) -> BoxFuture<'a, Result<DB::QueryResult>>
+    where
+        'c: 'a,
+        A: 'a + Send + Acquire<'c, Database = DB>,
+        Self: Sized,
+        Self::Identifier: Sync,
+        for<'e> &'e mut DB::Connection: Executor<'e, Database = DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    {
+        instrument::<Self, _, _>("delete_by_pks", async move {
+            let mut result = DB::QueryResult::default();
+            if pks.is_empty() {
+                return Ok(result);
+            }
+
+            let chunks = pks.chunks(Self::chunk_size());
+            if chunks.len() == 1 {
+                let mut connection = acquire.acquire().await?;
+                return delete_chunk::<Self, DB>(pks, &mut *connection).await;
+            }
+
+            let mut transaction = acquire.begin().await?;
+            for chunk in chunks {
+                result.extend(Some(
+                    delete_chunk::<Self, DB>(chunk, &mut *transaction).await?,
+                ));
+            }
+            transaction.commit().await?;
+            Ok(result)
+        })
+    }
+}
+
+/// This utility function deletes all entities with one of the given identifiers using a single
+/// statement.
+async fn delete_chunk<E, DB>(
+    chunk: &[E::Identifier],
+    connection: &mut DB::Connection,
+) -> Result<DB::QueryResult>
+where
+    E: ByPks<DB>,
+    DB: Database,
+    for<'e> &'e mut DB::Connection: Executor<'e, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+{
+    let sql = format!(
+        "DELETE FROM {} {};",
+        <E as SqlDescribe>::table_name(),
+        E::by_pks_condition(chunk.len())
+    );
+    let mut arguments = <DB as HasArguments<'_>>::Arguments::default();
+    E::bind_pks(chunk, &mut arguments);
+
+    query_with(&sql, arguments).execute(connection).await
+}
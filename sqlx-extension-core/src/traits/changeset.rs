@@ -0,0 +1,86 @@
+use futures_core::future::BoxFuture;
+use sqlx::database::HasArguments;
+use sqlx::{query_as_with, Database, Executor, FromRow, IntoArguments, Result};
+
+use super::bind_arguments::BindArguments;
+use super::entity::Entity;
+use super::sql_describe::SqlDescribe;
+use super::tenant_scoped::Unscoped;
+use crate::cipher::ensure_field_cipher;
+use crate::instrument::instrument;
+
+/// This trait describes a changeset, i.e. a set of fields of an entity which ought to be
+/// updated (e.g. the ones contained within a `PATCH` request). It should almost never be
+/// implemented directly, but rather using the `Describe` derive macro, which generates a
+/// changeset (e.g. `UserChangeset`) with every field neither annotated with `p_key`,
+/// `tenant` nor `generated` as `Option` for every struct annotated with
+/// `#[describe(changeset)]`.
+///
+/// Both `apply` and `Entity::update_where` require an entity which isn't scoped to a tenant,
+/// so changesets of tenant-scoped entities can't be applied (yet), since their statements
+/// wouldn't be restricted to the tenant.
+pub trait Changeset {
+    /// The entity the changeset belongs to.
+    type Entity: SqlDescribe;
+
+    /// This method returns a statement which updates all present fields of the entity
+    /// matching a given primary key, returning the updated entity. The placeholders of the
+    /// primary key follow the ones of the present fields. In case no field is present, no
+    /// statement is returned.
+    fn update_by_pk_returning(&self) -> Option<&'static str>;
+
+    /// This method returns a statement which updates all present fields, lacking both a
+    /// `WHERE` clause and the terminating semicolon, so that an arbitrary condition can be
+    /// appended (e.g. by `Entity::update_where`). The placeholders of the present fields start
+    /// at `$1`. In case no field is present, no statement is returned.
+    fn update_without_where(&self) -> Option<&'static str>;
+
+    /// This method returns the number of placeholders of the statement returned by
+    /// `update_without_where`, i.e. the number of columns of all present fields.
+    fn placeholder_count(&self) -> usize;
+
+    /// This method updates all present fields of the entity with the given primary key,
+    /// returning the updated entity (or `None` in case there is no such entity). In case no
+    /// field is present, the entity is merely retrieved. It fails in case the entity has
+    /// encrypted fields, but no cipher has been registered.
+    fn apply<'a, 'c, DB, X>(
+        &'a self,
+        pk: &'a <Self::Entity as Entity>::Identifier,
+        executor: X,
+    ) -> BoxFuture<'a, Result<Option<Self::Entity>>>
+    where
+        'c: 'a,
+        DB: Database,
+        X: 'a + Executor<'c, Database = DB>,
+        Self: Sync + BindChangeset<'a, DB>,
+        Self::Entity: Entity + Unscoped + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+        <Self::Entity as Entity>::Identifier: Sync + BindArguments<'a, DB>,
+        <DB as HasArguments<'a>>::Arguments: IntoArguments<'a, DB>,
+    {
+        instrument::<Self::Entity, _, _>("apply_changeset", async move {
+            let mut arguments = <DB as HasArguments<'a>>::Arguments::default();
+            let statement = match self.update_by_pk_returning() {
+                Some(statement) => {
+                    ensure_field_cipher::<Self::Entity>()?;
+                    self.bind_changes(&mut arguments);
+                    statement
+                }
+                None => <Self::Entity as SqlDescribe>::retrieve_by_pk(),
+            };
+            pk.bind_arguments(&mut arguments);
+
+            query_as_with(statement, arguments)
+                .fetch_optional(executor)
+                .await
+        })
+    }
+}
+
+/// This trait binds the present fields of a changeset to the arguments of the statement
+/// returned by `Changeset::update_by_pk_returning`. It should almost never be implemented
+/// directly, but rather using the `Describe` derive macro.
+pub trait BindChangeset<'q, DB: Database> {
+    /// This method binds all present fields, in the order expected by
+    /// `Changeset::update_by_pk_returning`.
+    fn bind_changes(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+}
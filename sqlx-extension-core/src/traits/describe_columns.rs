@@ -0,0 +1,25 @@
+use std::borrow::Cow;
+
+use sqlx::Database;
+
+/// This struct describes the rust type of a single column of an entity.
+pub struct ColumnType<DB: Database> {
+    /// The name of the column as stored within the database.
+    pub name: Cow<'static, str>,
+    /// The rust type of the corresponding field.
+    pub rust_type: &'static str,
+    /// Whether the field is an `Option`, i.e. accepts `NULL`.
+    pub nullable: bool,
+    /// This function returns the type information of the rust type.
+    pub type_info: fn() -> DB::TypeInfo,
+    /// This function checks whether the rust type can be decoded from the given database type.
+    pub compatible: fn(&DB::TypeInfo) -> bool,
+}
+
+/// This trait describes the rust types of all columns of an entity.
+/// It should almost never be implemented directly, but rather using the `Describe` derive
+/// macro.
+pub trait DescribeColumns<DB: Database> {
+    /// This function returns the types of all columns, in the order of the fields.
+    fn column_types() -> Vec<ColumnType<DB>>;
+}
@@ -1,15 +1,40 @@
+use futures_core::future::BoxFuture;
+use futures_core::stream::BoxStream;
 use sqlx::database::HasArguments;
 
+use sqlx::postgres::{PgArguments, PgRow};
 use sqlx::query::{Query, QueryAs};
-use sqlx::{query, query_as, Database, FromRow};
+use sqlx::{
+    query, query_as, query_scalar_with, query_with, Database, Executor, FromRow, IntoArguments,
+    Postgres, Result,
+};
 
+use super::bind_arguments::BindArguments;
+use super::changeset::{BindChangeset, Changeset};
+use super::postgres_dialect::PostgresDialect;
 use super::sql_describe::SqlDescribe;
+use super::tenant_scoped::Unscoped;
+use crate::aggregate::{fetch_aggregate, fetch_exists};
+use crate::cipher::ensure_field_cipher;
+use crate::cursor;
+use crate::filter::{BulkError, Filter, UnrestrictedFilter};
+use crate::group_by::GroupBy;
+use crate::instrument::{instrument, instrument_stream};
+use crate::maybe_transaction::MaybeTransaction;
 
 pub trait Entity: SqlDescribe {
     /// This is the type by which this entity is uniquely identified within
     /// the database.
     type Identifier;
 
+    /// This method returns the identifier of the current entity, i.e. the values of all
+    /// fields annotated with `p_key`.
+    ///
+    /// Note: This method has been added after the first release, so hand-written
+    /// implementations of this trait have to provide it as well. The `Entity` derive macro
+    /// implements it for every struct (which is required to have a primary key).
+    fn identifier(&self) -> Self::Identifier;
+
     /// This method generates a query which expects *all* attributes a certain entity
     /// has to be bound.
     fn insert<'q, DB: Database>() -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments> {
@@ -43,29 +68,246 @@ pub trait Entity: SqlDescribe {
     where
         R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
         DB: Database,
+        Self: Unscoped,
     {
         query_as(<Self as SqlDescribe>::retrieve_all())
     }
 
+    /// This function streams all entities within a given table, decoding them one after
+    /// another instead of loading all of them into memory at once.
+    fn stream_all<'e, 'c, DB, X>(executor: X) -> BoxStream<'e, Result<Self>>
+    where
+        'c: 'e,
+        DB: Database,
+        X: 'e + Executor<'c, Database = DB>,
+        Self: 'e + Unscoped + Sized + Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+        <DB as HasArguments<'e>>::Arguments: IntoArguments<'e, DB>,
+    {
+        let statement = <Self as SqlDescribe>::retrieve_all();
+        instrument_stream::<Self, _>(
+            "stream_all",
+            Some(statement),
+            query_as(statement).fetch(executor),
+        )
+    }
+
+    /// This function streams all entities matching the filter (if any) using a server-side
+    /// cursor within the given transaction, fetching `batch_size` rows at once. In contrast
+    /// to `stream_all`, the database doesn't have to hold the entire result at once.
+    fn stream_with_cursor<'a, 't>(
+        transaction: &'a mut MaybeTransaction<'t, Postgres>,
+        filter: Option<&'a Filter<Self>>,
+        batch_size: usize,
+    ) -> BoxStream<'a, Result<Self>>
+    where
+        Self: 'a + Unscoped + PostgresDialect + Sized + Send + Unpin + for<'r> FromRow<'r, PgRow>,
+    {
+        cursor::stream_with_cursor(transaction, filter, batch_size)
+    }
+
     /// This function retrieves a entity via its PK.
     fn retrieve_by_pk<'q, DB, R>() -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
     where
         DB: Database,
         R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
     {
         query_as(<Self as SqlDescribe>::retrieve_by_pk())
     }
 
+    /// This function retrieves a entity via its PK, locking the row using `FOR UPDATE`.
+    /// The query has to be executed within a transaction (e.g. the one of a `MaybeTransaction`).
+    fn retrieve_by_pk_for_update<'q, DB, R>(
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update())
+    }
+
+    /// This function retrieves a entity via its PK, locking the row using `FOR SHARE`.
+    /// The query has to be executed within a transaction (e.g. the one of a `MaybeTransaction`).
+    fn retrieve_by_pk_for_share<'q, DB, R>(
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_share())
+    }
+
+    /// This function retrieves a entity via its PK, locking the row using `FOR UPDATE NOWAIT`,
+    /// failing in case it is already locked.
+    /// The query has to be executed within a transaction (e.g. the one of a `MaybeTransaction`).
+    fn retrieve_by_pk_for_update_nowait<'q, DB, R>(
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update_nowait())
+    }
+
+    /// This function retrieves a entity via its PK, locking the row using `FOR UPDATE SKIP LOCKED`,
+    /// resolving to no row in case it is already locked.
+    /// The query has to be executed within a transaction (e.g. the one of a `MaybeTransaction`).
+    fn retrieve_by_pk_for_update_skip_locked<'q, DB, R>(
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update_skip_locked())
+    }
+
+    /// This function updates all fields which are not part of the PK of a single entity.
+    fn update_by_pk<'q, DB>() -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self: Unscoped,
+    {
+        query(<Self as SqlDescribe>::update_by_pk())
+    }
+
     /// This function deletes a single entity via the PK of the table.
     fn delete_by_pk<'q, DB>() -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
     where
         DB: Database,
+        Self: Unscoped,
+    {
+        query(<Self as SqlDescribe>::delete_by_pk())
+    }
+
+    /// This function updates the present fields of the changeset for all entities matching
+    /// the filter, returning the number of affected rows. A filter without any condition is
+    /// refused with `BulkError::Unrestricted`, so the entire table can't be updated by
+    /// accident; use `update_all` in case this is intended. In case no field is present,
+    /// nothing is updated.
+    fn update_where<'a, 'c, C, X>(
+        filter: &'a Filter<Self>,
+        changes: &'a C,
+        executor: X,
+    ) -> BoxFuture<'a, std::result::Result<u64, BulkError>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        C: Sync + Changeset<Entity = Self> + BindChangeset<'a, Postgres>,
+        Self: Unscoped + PostgresDialect + Sized,
+    {
+        let operation = instrument::<Self, _, _>("update_where", async move {
+            update_matching(filter, changes, executor).await
+        });
+        restricted(filter, operation)
+    }
+
+    /// This function updates the present fields of the changeset for *all* entities,
+    /// returning the number of affected rows.
+    fn update_all<'a, 'c, C, X>(changes: &'a C, executor: X) -> BoxFuture<'a, Result<u64>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        C: Sync + Changeset<Entity = Self> + BindChangeset<'a, Postgres>,
+        Self: Unscoped + PostgresDialect + Sized + 'static,
     {
-        query(<Self as SqlDescribe>::retrieve_by_pk())
+        instrument::<Self, _, _>("update_all", async move {
+            update_matching(&Filter::<Self>::all(), changes, executor).await
+        })
+    }
+
+    /// This function deletes all entities matching the filter, returning the number of
+    /// affected rows. A filter without any condition is refused with
+    /// `BulkError::Unrestricted`, so the entire table can't be deleted by accident; use
+    /// `delete_all` in case this is intended.
+    fn delete_where<'a, 'c, X>(
+        filter: &'a Filter<Self>,
+        executor: X,
+    ) -> BoxFuture<'a, std::result::Result<u64, BulkError>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        Self: Unscoped + PostgresDialect + Sized,
+    {
+        let operation = instrument::<Self, _, _>("delete_where", async move {
+            delete_matching(filter, executor).await
+        });
+        restricted(filter, operation)
+    }
+
+    /// This function deletes *all* entities, returning the number of affected rows.
+    fn delete_all<'a, 'c, X>(executor: X) -> BoxFuture<'a, Result<u64>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        Self: Unscoped + PostgresDialect + Sized + 'static,
+    {
+        instrument::<Self, _, _>("delete_all", async move {
+            delete_matching(&Filter::<Self>::all(), executor).await
+        })
+    }
+
+    /// This function counts all entities matching the filter (if any).
+    fn count<'a, 'c, X>(filter: Option<&'a Filter<Self>>, executor: X) -> BoxFuture<'a, Result<i64>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        Self: Unscoped + PostgresDialect + Sized,
+    {
+        instrument::<Self, _, _>("count", async move {
+            fetch_aggregate("COUNT(*)", filter, executor).await
+        })
+    }
+
+    /// This function checks whether any entity matches the filter (if any).
+    fn exists<'a, 'c, X>(
+        filter: Option<&'a Filter<Self>>,
+        executor: X,
+    ) -> BoxFuture<'a, Result<bool>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        Self: Unscoped + PostgresDialect + Sized,
+    {
+        instrument::<Self, _, _>(
+            "exists",
+            async move { fetch_exists(filter, executor).await },
+        )
+    }
+
+    /// This function checks whether an entity with the given primary key exists.
+    fn exists_by_pk<'a, 'c, X>(pk: &'a Self::Identifier, executor: X) -> BoxFuture<'a, Result<bool>>
+    where
+        'c: 'a,
+        X: 'a + Executor<'c, Database = Postgres>,
+        Self: Unscoped + PostgresDialect,
+        Self::Identifier: Sync + for<'q> BindArguments<'q, Postgres>,
+    {
+        instrument::<Self, _, _>("exists_by_pk", async move {
+            let sql = format!(
+                "SELECT EXISTS ({});",
+                <Self as SqlDescribe>::retrieve_by_pk().trim_end_matches(';')
+            );
+            let mut arguments = PgArguments::default();
+            pk.bind_arguments(&mut arguments);
+
+            query_scalar_with(&sql, arguments).fetch_one(executor).await
+        })
+    }
+
+    /// This function creates a builder for a `GROUP BY` query over the table of the entity.
+    fn group_by() -> GroupBy<Self>
+    where
+        Self: Unscoped + PostgresDialect + Sized + 'static,
+    {
+        GroupBy::new()
     }
 
     /// This function generates a query with the given SQL, resolving to the passed type.
-    fn custom_query_as<'q, DB: Database, O>(
+    fn custom_query_as<'q, DB, O>(
         sql: &'q str,
     ) -> QueryAs<'q, DB, O, <DB as HasArguments<'q>>::Arguments>
     where
@@ -76,7 +318,7 @@ pub trait Entity: SqlDescribe {
     }
 
     /// This function generates a query with the given SQL, resolving to the passed type.
-    fn custom_query_as_self<'q, DB: Database>(
+    fn custom_query_as_self<'q, DB>(
         sql: &'q str,
     ) -> QueryAs<'q, DB, Self, <DB as HasArguments<'q>>::Arguments>
     where
@@ -86,3 +328,74 @@ pub trait Entity: SqlDescribe {
         query_as(sql)
     }
 }
+
+/// This utility function executes the given bulk operation, unless the filter lacks any
+/// restricting condition, in which case the operation is refused (without being executed).
+fn restricted<'a, E: SqlDescribe>(
+    filter: &Filter<E>,
+    operation: BoxFuture<'a, Result<u64>>,
+) -> BoxFuture<'a, std::result::Result<u64, BulkError>> {
+    let unrestricted = filter.is_all();
+    Box::pin(async move {
+        if unrestricted {
+            return Err(BulkError::Unrestricted(UnrestrictedFilter::new(
+                E::table_name(),
+            )));
+        }
+        Ok(operation.await?)
+    })
+}
+
+/// This utility function updates the present fields of the changeset for all entities
+/// matching the filter, whose placeholders follow the ones of the changeset.
+async fn update_matching<'a, 'c, E, C, X>(
+    filter: &Filter<E>,
+    changes: &'a C,
+    executor: X,
+) -> Result<u64>
+where
+    E: SqlDescribe,
+    X: Executor<'c, Database = Postgres>,
+    C: Changeset + BindChangeset<'a, Postgres>,
+{
+    let statement = match changes.update_without_where() {
+        Some(statement) => statement,
+        None => return Ok(0),
+    };
+    ensure_field_cipher::<C::Entity>()?;
+    let sql = format!(
+        "{} {};",
+        statement,
+        filter.where_clause(changes.placeholder_count() + 1)
+    );
+
+    let mut arguments = PgArguments::default();
+    changes.bind_changes(&mut arguments);
+    filter.bind(&mut arguments);
+
+    Ok(query_with(&sql, arguments)
+        .execute(executor)
+        .await?
+        .rows_affected())
+}
+
+/// This utility function deletes all entities matching the filter.
+async fn delete_matching<'c, E, X>(filter: &Filter<E>, executor: X) -> Result<u64>
+where
+    E: SqlDescribe,
+    X: Executor<'c, Database = Postgres>,
+{
+    let sql = format!(
+        "DELETE FROM {} {};",
+        E::table_name(),
+        filter.where_clause(1)
+    );
+
+    let mut arguments = PgArguments::default();
+    filter.bind(&mut arguments);
+
+    Ok(query_with(&sql, arguments)
+        .execute(executor)
+        .await?
+        .rows_affected())
+}
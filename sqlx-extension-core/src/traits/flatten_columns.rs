@@ -0,0 +1,24 @@
+use sqlx::{Result, Row};
+
+use crate::statements::StatementColumn;
+
+/// This trait describes the columns a struct is expanded into when it is embedded into an
+/// entity using the `flatten` attribute.
+/// It should almost never be implemented directly, but rather using the `Describe` derive
+/// macro.
+pub trait FlattenColumns {
+    /// This function returns the columns of all fields (in their order), each prefixed by
+    /// the given prefix. The columns are neither part of the primary key nor contain the
+    /// tenant of the embedding entity.
+    fn flatten_columns(prefix: &str) -> Vec<StatementColumn>;
+}
+
+/// This trait decodes a struct from a row whose columns are prefixed by a given prefix, which
+/// is how flattened fields are decoded. It is implemented by the `Describe` derive macro,
+/// which also implements `FromRow` (using an empty prefix) for every entity with flattened
+/// fields.
+pub trait FromPrefixedRow<'r, R: Row>: Sized {
+    /// This function decodes the struct from the columns of the given row, each prefixed by
+    /// the given prefix.
+    fn from_prefixed_row(row: &'r R, prefix: &str) -> Result<Self>;
+}
@@ -1,2 +1,14 @@
+pub mod bind_arguments;
+pub mod bind_fields;
+pub mod by_pks;
+pub mod changeset;
+pub mod describe_columns;
 pub mod entity;
+pub mod flatten_columns;
+pub mod new_entity;
+pub mod postgres_dialect;
+pub mod render_statements;
 pub mod sql_describe;
+pub mod tenant_entity;
+pub mod tenant_scoped;
+pub mod track_changes;
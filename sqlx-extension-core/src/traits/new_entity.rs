@@ -0,0 +1,51 @@
+use futures_core::future::BoxFuture;
+use sqlx::database::HasArguments;
+use sqlx::{query_as_with, Database, Executor, FromRow, IntoArguments, Result};
+
+use super::sql_describe::SqlDescribe;
+use crate::cipher::ensure_field_cipher;
+use crate::instrument::instrument;
+
+/// This trait describes the fields required to insert an entity, i.e. all fields neither
+/// annotated with `p_key` nor `generated`. It should almost never be implemented directly,
+/// but rather using the `Describe` derive macro, which generates such a struct (e.g.
+/// `NewUser`) for every struct annotated with `#[describe(new)]`.
+pub trait NewEntity {
+    /// The entity which is inserted.
+    type Entity: SqlDescribe;
+
+    /// This method inserts the entity using `SqlDescribe::insert_without_pk`, returning the
+    /// entire inserted entity (including its primary key and all generated fields). It fails
+    /// in case the entity has encrypted fields, but no cipher has been registered.
+    fn insert<'a, 'c, DB, X>(&'a self, executor: X) -> BoxFuture<'a, Result<Self::Entity>>
+    where
+        'c: 'a,
+        DB: Database,
+        X: 'a + Executor<'c, Database = DB>,
+        Self: Sync + BindNewEntity<'a, DB>,
+        Self::Entity: Send + Unpin + for<'r> FromRow<'r, DB::Row>,
+        <DB as HasArguments<'a>>::Arguments: IntoArguments<'a, DB>,
+    {
+        instrument::<Self::Entity, _, _>("insert", async move {
+            ensure_field_cipher::<Self::Entity>()?;
+            let mut arguments = <DB as HasArguments<'a>>::Arguments::default();
+            self.bind_new(&mut arguments);
+
+            query_as_with(
+                <Self::Entity as SqlDescribe>::insert_without_pk(),
+                arguments,
+            )
+            .fetch_one(executor)
+            .await
+        })
+    }
+}
+
+/// This trait binds the fields of an insert DTO to the arguments of the statement returned
+/// by `SqlDescribe::insert_without_pk`. It should almost never be implemented directly, but
+/// rather using the `Describe` derive macro.
+pub trait BindNewEntity<'q, DB: Database> {
+    /// This method binds all fields, in the order expected by
+    /// `SqlDescribe::insert_without_pk`.
+    fn bind_new(&'q self, arguments: &mut <DB as HasArguments<'q>>::Arguments);
+}
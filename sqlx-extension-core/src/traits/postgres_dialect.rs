@@ -0,0 +1,6 @@
+/// This marker trait is implemented by the `Describe` derive macro for every entity whose
+/// statements are rendered for Postgres (the default dialect). The functions binding filters
+/// or requiring a Postgres executor (e.g. `Entity::delete_where`, `Column::sum`, `GroupBy`
+/// and `Entity::stream_with_cursor`) require it, so they can't be used with the statements
+/// of another dialect.
+pub trait PostgresDialect {}
@@ -0,0 +1,11 @@
+use crate::statements::Statements;
+
+/// This trait renders all statements of an entity at runtime, e.g. against the schema of a
+/// tenant which is only known at runtime (see `TenantSchema::statements`).
+/// It should almost never be implemented directly, but rather using the `Describe` derive
+/// macro.
+pub trait RenderStatements {
+    /// This function renders all statements of the entity against the given schema; without
+    /// a schema, the one given at compile time (if any) is used.
+    fn render_statements(schema_name: Option<&str>) -> Statements;
+}
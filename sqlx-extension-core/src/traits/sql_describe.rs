@@ -1,3 +1,5 @@
+use crate::dialect::Dialect;
+
 /// This trait describes a given entity using SQL statements.
 /// It should almost never be implemented directly, but rather using the derive macro
 /// which comes with this trait.
@@ -7,12 +9,15 @@ pub trait SqlDescribe {
     fn insert() -> &'static str;
 
     /// This function returns a statement which inserts the entity without
-    /// requiring the fields annoted with `p_key`, returning the entire entity.
+    /// requiring the fields annoted with `p_key` (or `generated`), returning the entire entity.
+    /// The statement is empty (and can't be executed) in case the dialect doesn't support
+    /// `RETURNING` (i.e. for MySQL).
     fn insert_without_pk() -> &'static str;
 
     /// This function returns a statement which inserts the entity without
     /// requiring the fields annotated with `p_key`, returning *just* the fields
-    /// annotated with `p_key`.
+    /// annotated with `p_key`. The statement is empty (and can't be executed) in case the
+    /// dialect doesn't support `RETURNING` (i.e. for MySQL).
     fn insert_without_pk_return_pk() -> &'static str;
 
     /// This function returns a statement which retrieves all entities from
@@ -23,10 +28,92 @@ pub trait SqlDescribe {
     /// the entities by the primary key of the entity.
     fn retrieve_by_pk() -> &'static str;
 
+    /// This function returns a statement which retrieves an entity by its primary key,
+    /// locking the row using `FOR UPDATE`. Just like the other locking statements, it is
+    /// empty (and can't be executed) in case the dialect doesn't support row locks (i.e. for
+    /// SQLite).
+    fn retrieve_by_pk_for_update() -> &'static str;
+
+    /// This function returns a statement which retrieves an entity by its primary key,
+    /// locking the row using `FOR SHARE`.
+    fn retrieve_by_pk_for_share() -> &'static str;
+
+    /// This function returns a statement which retrieves an entity by its primary key,
+    /// locking the row using `FOR UPDATE NOWAIT`.
+    fn retrieve_by_pk_for_update_nowait() -> &'static str;
+
+    /// This function returns a statement which retrieves an entity by its primary key,
+    /// locking the row using `FOR UPDATE SKIP LOCKED`.
+    fn retrieve_by_pk_for_update_skip_locked() -> &'static str;
+
+    /// This function returns a statement which updates all fields which are not annotated
+    /// with `p_key` (or `generated`) of the entity matching a given primary key. The
+    /// placeholders of the primary key follow the ones of the updated fields.
+    /// The statement is empty (and can't be executed) in case the entity has no primary key
+    /// or consists of primary key fields only, since there is no row to restrict or nothing
+    /// to set.
+    fn update_by_pk() -> &'static str;
+
     /// This function returns a statement which deletes all entities in the table
-    /// matching a given primary key.
+    /// matching a given primary key. The statement is empty (and can't be executed) in case
+    /// the entity has no primary key, as it would delete the entire table otherwise.
     fn delete_by_pk() -> &'static str;
 
-    /// This function returns the name of the table for the current entity.
+    /// This function returns the list of columns selected by the retrieve statements, which
+    /// is `*` unless a column is read with an expression.
+    fn select_list() -> &'static str {
+        "*"
+    }
+
+    /// This function returns the dialect the statements are rendered for, which the
+    /// placeholders of filters (e.g. within `Entity::delete_where`) are rendered for as well.
+    fn dialect() -> Dialect {
+        Dialect::Postgres
+    }
+
+    /// This function returns whether the entity has fields annotated with `encrypted`
+    /// (including the ones of flattened fields), which can't be bound without a cipher.
+    fn has_encrypted_fields() -> bool {
+        false
+    }
+
+    /// This function returns all statements alongside the names of their functions, e.g. to
+    /// list them within the statement catalog.
+    fn statements() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("insert", Self::insert()),
+            ("insert_without_pk", Self::insert_without_pk()),
+            (
+                "insert_without_pk_return_pk",
+                Self::insert_without_pk_return_pk(),
+            ),
+            ("retrieve_all", Self::retrieve_all()),
+            ("retrieve_by_pk", Self::retrieve_by_pk()),
+            (
+                "retrieve_by_pk_for_update",
+                Self::retrieve_by_pk_for_update(),
+            ),
+            ("retrieve_by_pk_for_share", Self::retrieve_by_pk_for_share()),
+            (
+                "retrieve_by_pk_for_update_nowait",
+                Self::retrieve_by_pk_for_update_nowait(),
+            ),
+            (
+                "retrieve_by_pk_for_update_skip_locked",
+                Self::retrieve_by_pk_for_update_skip_locked(),
+            ),
+            ("update_by_pk", Self::update_by_pk()),
+            ("delete_by_pk", Self::delete_by_pk()),
+        ]
+    }
+
+    /// This function returns the name of the table for the current entity, quoted and
+    /// qualified by its schema (if any), so it can be interpolated into a statement.
     fn table_name() -> &'static str;
+
+    /// This function returns the quoted name of the table without its schema. It has to be
+    /// overridden in case `table_name` is qualified by a schema.
+    fn table_name_without_schema() -> &'static str {
+        Self::table_name()
+    }
 }
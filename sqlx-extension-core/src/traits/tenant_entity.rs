@@ -0,0 +1,145 @@
+use sqlx::database::HasArguments;
+
+use sqlx::query::{Query, QueryAs};
+use sqlx::{query, query_as, Database, FromRow};
+
+use super::entity::Entity;
+use super::tenant_scoped::Unscoped;
+use crate::tenant::TenantStatements;
+
+/// This trait provides tenant-aware variants of the query functions of `Entity`, which
+/// execute the statements rendered against the table within the schema of a tenant (see
+/// `TenantSchema::statements`). It is implemented for every entity.
+pub trait TenantEntity: Entity {
+    /// This method generates a query which expects *all* attributes a certain entity
+    /// has to be bound, inserting into the table of the given tenant.
+    fn insert_in<'q, DB: Database>(
+        statements: &'q TenantStatements<Self>,
+    ) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments> {
+        query(&statements.insert)
+    }
+
+    /// This function inserts the entity without the annotated primary keys into the table
+    /// of the given tenant, returning the entire entity.
+    fn insert_without_pk_in<'q, DB>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, Self, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self: Send + Sized + for<'r> sqlx::FromRow<'r, DB::Row>,
+    {
+        query_as(&statements.insert_without_pk)
+    }
+
+    /// This function inserts the entity without the annotated primary keys into the table
+    /// of the given tenant, returning the primary key of the entity.
+    fn insert_without_pk_return_pk_in<'q, DB: Database>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, Self::Identifier, <DB as HasArguments<'q>>::Arguments>
+    where
+        Self::Identifier: for<'r> FromRow<'r, DB::Row>,
+    {
+        query_as(&statements.insert_without_pk_return_pk)
+    }
+
+    /// This function retrieves all entities within the table of the given tenant.
+    fn retrieve_all_in<'q, R, DB>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        DB: Database,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_all)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK.
+    fn retrieve_by_pk_in<'q, DB, R>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_by_pk)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE`.
+    fn retrieve_by_pk_for_update_in<'q, DB, R>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_by_pk_for_update)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR SHARE`.
+    fn retrieve_by_pk_for_share_in<'q, DB, R>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_by_pk_for_share)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE NOWAIT`.
+    fn retrieve_by_pk_for_update_nowait_in<'q, DB, R>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_by_pk_for_update_nowait)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE SKIP LOCKED`.
+    fn retrieve_by_pk_for_update_skip_locked_in<'q, DB, R>(
+        statements: &'q TenantStatements<Self>,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self: Unscoped,
+    {
+        query_as(&statements.retrieve_by_pk_for_update_skip_locked)
+    }
+
+    /// This function updates all fields which are not part of the PK of a single entity of
+    /// the given tenant.
+    fn update_by_pk_in<'q, DB>(
+        statements: &'q TenantStatements<Self>,
+    ) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self: Unscoped,
+    {
+        query(&statements.update_by_pk)
+    }
+
+    /// This function deletes a single entity of the given tenant via the PK of the table.
+    fn delete_by_pk_in<'q, DB>(
+        statements: &'q TenantStatements<Self>,
+    ) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self: Unscoped,
+    {
+        query(&statements.delete_by_pk)
+    }
+}
+
+impl<E: Entity> TenantEntity for E {}
@@ -0,0 +1,121 @@
+use sqlx::database::HasArguments;
+
+use sqlx::query::{Query, QueryAs};
+use sqlx::{query, query_as, Database, Encode, FromRow, Type};
+
+use super::sql_describe::SqlDescribe;
+
+/// This marker trait is implemented by the `Describe` derive macro for every entity without
+/// a `tenant` field. The retrieve, update and delete functions of `Entity` require it, so the
+/// tenant of a tenant-scoped entity can't be forgotten.
+pub trait Unscoped {}
+
+/// This trait is implemented by the `Describe` derive macro for every entity with a `tenant`
+/// field. Its functions bind the given tenant to the first placeholder of the generated
+/// statements, so only the remaining parameters have to be bound.
+pub trait TenantScoped: SqlDescribe {
+    /// The type of the tenant field.
+    type Tenant;
+
+    /// This method returns the tenant the entity belongs to.
+    fn tenant(&self) -> &Self::Tenant;
+
+    /// This function retrieves all entities of the given tenant.
+    fn retrieve_all_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_all()).bind(tenant)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK.
+    fn retrieve_by_pk_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk()).bind(tenant)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE`.
+    fn retrieve_by_pk_for_update_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update()).bind(tenant)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR SHARE`.
+    fn retrieve_by_pk_for_share_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_share()).bind(tenant)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE NOWAIT`.
+    fn retrieve_by_pk_for_update_nowait_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update_nowait()).bind(tenant)
+    }
+
+    /// This function retrieves a entity of the given tenant via its PK, locking the row
+    /// using `FOR UPDATE SKIP LOCKED`.
+    fn retrieve_by_pk_for_update_skip_locked_of_tenant<'q, DB, R>(
+        tenant: Self::Tenant,
+    ) -> QueryAs<'q, DB, R, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        R: for<'r> FromRow<'r, DB::Row> + Send + Unpin,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query_as(<Self as SqlDescribe>::retrieve_by_pk_for_update_skip_locked()).bind(tenant)
+    }
+
+    /// This function updates all fields which are neither part of the PK nor the tenant of
+    /// a single entity of the given tenant.
+    fn update_by_pk_of_tenant<'q, DB>(
+        tenant: Self::Tenant,
+    ) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query(<Self as SqlDescribe>::update_by_pk()).bind(tenant)
+    }
+
+    /// This function deletes a single entity of the given tenant via the PK of the table.
+    fn delete_by_pk_of_tenant<'q, DB>(
+        tenant: Self::Tenant,
+    ) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+    where
+        DB: Database,
+        Self::Tenant: 'q + Send + Encode<'q, DB> + Type<DB>,
+    {
+        query(<Self as SqlDescribe>::delete_by_pk()).bind(tenant)
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::{self, Debug, Formatter};
+
+use super::changeset::Changeset;
+
+/// This trait derives a changeset from an entity and the flags of the fields which have been
+/// changed, which allows updating just these fields (see `Tracked`). It should almost never
+/// be implemented directly, but rather using the `Describe` derive macro, which implements it
+/// alongside the changeset (see `#[describe(changeset)]`).
+pub trait TrackChanges: Sized {
+    /// The changeset of the entity.
+    type Changeset: Changeset<Entity = Self>;
+
+    /// The names of all fields which can be changed, i.e. the fields of the changeset in
+    /// their order. The flags passed to `changes` follow this order.
+    const TRACKED_FIELDS: &'static [&'static str];
+
+    /// This method returns a changeset containing all fields whose flag is set.
+    fn changes(&self, dirty: &[bool]) -> Self::Changeset;
+}
+
+/// This struct describes a single field which can be changed through `Tracked`, combining
+/// the position of the field within `TrackChanges::TRACKED_FIELDS` with a mutable accessor.
+/// The `Describe` derive macro generates an associated constant on the changeset for every
+/// field (e.g. `UserChangeset::EMAIL`).
+pub struct TrackedField<E, T> {
+    index: usize,
+    name: &'static str,
+    accessor: fn(&mut E) -> &mut T,
+}
+
+impl<E, T> TrackedField<E, T> {
+    /// This function creates a new field with the given position, name and accessor.
+    pub const fn new(index: usize, name: &'static str, accessor: fn(&mut E) -> &mut T) -> Self {
+        Self {
+            index,
+            name,
+            accessor,
+        }
+    }
+
+    /// This method returns the position of the field within `TrackChanges::TRACKED_FIELDS`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// This method returns the name of the field.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// This method returns the value of the field for the given entity.
+    pub fn get_mut<'e>(&self, entity: &'e mut E) -> &'e mut T {
+        (self.accessor)(entity)
+    }
+}
+
+impl<E, T> Clone for TrackedField<E, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, T> Copy for TrackedField<E, T> {}
+
+impl<E, T> Debug for TrackedField<E, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackedField")
+            .field("index", &self.index)
+            .field("name", &self.name)
+            .finish()
+    }
+}
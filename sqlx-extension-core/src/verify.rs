@@ -0,0 +1,148 @@
+//! This module verifies at runtime that the fields of an entity match the columns of its
+//! table, both in type and nullability.
+
+use std::fmt::{self, Display, Formatter};
+
+use sqlx::{Column, Executor, PgPool, Postgres, Result, TypeInfo};
+
+use crate::traits::{describe_columns::DescribeColumns, sql_describe::SqlDescribe};
+
+/// This enum represents a single difference between a field and its column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The column of the field doesn't exist within the table.
+    MissingColumn { column: String },
+    /// The rust type of the field can't be decoded from the type of the column.
+    IncompatibleType {
+        column: String,
+        rust_type: String,
+        sql_type: String,
+    },
+    /// The column is nullable, but the field isn't an `Option` (or vice versa).
+    Nullability {
+        column: String,
+        rust_type: String,
+        column_nullable: bool,
+    },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::MissingColumn { column } => write!(f, "column `{}` doesn't exist", column),
+            Mismatch::IncompatibleType {
+                column,
+                rust_type,
+                sql_type,
+            } => write!(
+                f,
+                "column `{}` of type `{}` is incompatible with `{}`",
+                column, sql_type, rust_type
+            ),
+            Mismatch::Nullability {
+                column,
+                rust_type,
+                column_nullable: true,
+            } => write!(
+                f,
+                "column `{}` is nullable, but `{}` is not an `Option`",
+                column, rust_type
+            ),
+            Mismatch::Nullability {
+                column, rust_type, ..
+            } => write!(
+                f,
+                "column `{}` is not nullable, but `{}` is an `Option`",
+                column, rust_type
+            ),
+        }
+    }
+}
+
+/// This struct contains all mismatches between an entity and its table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaReport {
+    /// The name of the verified table.
+    pub table: &'static str,
+    /// All mismatches which were found, in the order of the fields.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl SchemaReport {
+    /// This method returns whether the entity matches its table.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl Display for SchemaReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "table `{}` matches its entity", self.table);
+        }
+
+        write!(f, "table `{}` doesn't match its entity:", self.table)?;
+        for mismatch in &self.mismatches {
+            write!(f, "\n  - {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// This function compares the types of all fields of the given entity against the columns
+/// of its table, returning a report of every mismatch.
+/// An error is only returned in case the table couldn't be described at all (e.g. because
+/// it doesn't exist).
+pub async fn verify_schema<E>(pool: &PgPool) -> Result<SchemaReport>
+where
+    E: SqlDescribe + DescribeColumns<Postgres>,
+{
+    let sql = format!("SELECT * FROM {}", E::table_name());
+    let describe = pool.describe(&sql).await?;
+
+    let mismatches = E::column_types()
+        .into_iter()
+        .filter_map(|field| {
+            // find the corresponding column
+            let index = describe
+                .columns()
+                .iter()
+                .position(|c| c.name() == field.name);
+            let index = match index {
+                Some(index) => index,
+                None => {
+                    return Some(Mismatch::MissingColumn {
+                        column: field.name.into_owned(),
+                    })
+                }
+            };
+
+            // compare the types
+            let sql_type = describe.column(index).type_info();
+            if !(field.compatible)(sql_type) {
+                return Some(Mismatch::IncompatibleType {
+                    column: field.name.into_owned(),
+                    rust_type: field.rust_type.into(),
+                    sql_type: sql_type.name().into(),
+                });
+            }
+
+            // compare the nullability (if known)
+            match describe.nullable(index) {
+                Some(column_nullable) if column_nullable != field.nullable => {
+                    Some(Mismatch::Nullability {
+                        column: field.name.into_owned(),
+                        rust_type: field.rust_type.into(),
+                        column_nullable,
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    Ok(SchemaReport {
+        table: E::table_name(),
+        mismatches,
+    })
+}
@@ -1,26 +1,106 @@
+mod by_pks;
+mod key;
+
 use proc_macro::TokenStream;
-use quote::quote;
-use sqlx_extension_core::models::attribute::Attribute;
-use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Type};
+use quote::{format_ident, quote, quote_spanned};
+use sqlx_extension_core::models::{attribute::Attribute, field::Field};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DataStruct, DeriveInput, Fields, Type};
+
+use crate::utils::{parse_attributes, parse_dialect, parse_fields, parse_rename_rule};
 
-use crate::utils::parse_attributes;
+use self::by_pks::create_by_pks_impl;
+use self::key::create_key_struct;
 
 pub fn entity_macro(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let res: Option<TokenStream> = match input.data {
-        Data::Struct(DataStruct { .. }) => {
+        Data::Struct(DataStruct { fields, .. }) => {
             let struct_name = &input.ident;
-            let struct_attributes: Vec<_> = parse_attributes(input.attrs).collect();
+            let struct_attributes = match parse_attributes(input.attrs) {
+                Ok(attributes) => attributes,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            let rename_rule = parse_rename_rule(struct_attributes.iter());
 
-            // parse identifier for table (if any)
-            parse_identifier(struct_attributes.iter()).map(|i| {
-                let result = quote! {
-                    impl sqlx_extension::sqlx_extension_core::traits::entity::Entity for #struct_name {
-                        type Identifier = #i;
+            let dialect = parse_dialect(struct_attributes.iter());
+
+            // parse the fields (alongside their visibility)
+            let all_fields = match fields {
+                Fields::Named(fields_named) => {
+                    match parse_fields(fields_named.named, rename_rule) {
+                        Ok(fields) => fields,
+                        Err(e) => return e.to_compile_error().into(),
                     }
-                };
-                result.into()
-            })
+                }
+                _ => Vec::new(),
+            };
+            let has_tenant = all_fields
+                .iter()
+                .any(|(_, f)| f.attributes.contains(&Attribute::Tenant));
+
+            // the fields which make up the primary key
+            let fields_in_pk = all_fields
+                .into_iter()
+                .filter(|(_, f)| f.attributes.contains(&Attribute::PrimaryKey))
+                .collect::<Vec<_>>();
+
+            // without a primary key, entities can't be told apart (and `update_by_pk` as well
+            // as `delete_by_pk` would affect the entire table)
+            if fields_in_pk.is_empty() {
+                return syn::Error::new_spanned(
+                    struct_name,
+                    "`Entity` requires at least one field annotated with `p_key`",
+                )
+                .to_compile_error()
+                .into();
+            }
+
+            // parse identifier for table; in case there is none, it is inferred from the
+            // type of a single primary key or a key struct is generated for multiple ones
+            let (ident_type, key_struct) = match parse_identifier(struct_attributes.iter()) {
+                Some(i) => (i.clone(), None),
+                None if fields_in_pk.len() == 1 => {
+                    let ty = &fields_in_pk[0].1.ty;
+                    (parse_quote!((#ty,)), None)
+                }
+                None => {
+                    let key_name = format_ident!("{}Key", struct_name);
+                    let key_struct =
+                        create_key_struct(struct_name, &key_name, &input.vis, &fields_in_pk);
+                    (parse_quote!(#key_name), Some(key_struct))
+                }
+            };
+
+            let custom_identifier = parse_identifier(struct_attributes.iter()).is_some();
+            let fields_in_pk = fields_in_pk.into_iter().map(|(_, f)| f).collect::<Vec<_>>();
+            let identifier = create_identifier_fn(&ident_type, &fields_in_pk);
+
+            // the entities of a tenant can't be looked up without the tenant
+            let by_pks = if has_tenant {
+                None
+            } else {
+                let fields_ref = fields_in_pk.iter().collect::<Vec<_>>();
+                Some(create_by_pks_impl(
+                    struct_name,
+                    &ident_type,
+                    &fields_ref,
+                    custom_identifier,
+                    dialect,
+                ))
+            };
+            let result = quote! {
+                #key_struct
+
+                impl sqlx_extension::sqlx_extension_core::traits::entity::Entity for #struct_name {
+                    type Identifier = #ident_type;
+
+                    #identifier
+                }
+
+                #by_pks
+            };
+            Some(result.into())
         }
         _ => None,
     };
@@ -32,9 +112,26 @@ pub fn entity_macro(input: TokenStream) -> TokenStream {
 fn parse_identifier<'a>(attrs: impl IntoIterator<Item = &'a Attribute> + 'a) -> Option<&'a Type> {
     attrs.into_iter().find_map(|a| {
         if let Attribute::Identifier { ident_type } = a {
-            Some(ident_type)
+            Some(&**ident_type)
         } else {
             None
         }
     })
 }
+
+/// This function creates the `identifier` method, which converts a tuple of all primary key
+/// fields into the identifier type. The conversion is spanned on the identifier type, so that
+/// a custom identifier lacking the `From` implementation is reported at `#[ident(...)]`.
+fn create_identifier_fn(ident_type: &Type, fields_in_pk: &[Field]) -> proc_macro2::TokenStream {
+    let types = fields_in_pk.iter().map(|f| &f.ty);
+    let idents = fields_in_pk.iter().map(|f| &f.identifier);
+    let conversion = quote_spanned! {ident_type.span()=>
+        <#ident_type as ::std::convert::From<(#(#types,)*)>>::from
+    };
+
+    quote! {
+        fn identifier(&self) -> Self::Identifier {
+            #conversion((#(::std::clone::Clone::clone(&self.#idents),)*))
+        }
+    }
+}
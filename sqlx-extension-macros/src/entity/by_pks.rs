@@ -0,0 +1,105 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::{dialect::Dialect, models::field::Field, query_builder::QueryBuilder};
+use syn::{Ident, Type};
+
+use crate::sql_describe::flatten::{create_columns, create_dialect};
+
+/// The types which are known to be bound as an array on Postgres.
+const ARRAY_TYPES: &[&str] = &[
+    "bool", "i8", "i16", "i32", "i64", "f32", "f64", "String", "Uuid",
+];
+
+/// This function creates the implementation of `ByPks` for the given struct. On Postgres, a
+/// primary key consisting of a single field of a type which can be bound as an array (see
+/// `ARRAY_TYPES`) is compared to an array of all identifiers (as long as the identifier is
+/// the default one, i.e. a tuple containing the field). Else, the identifiers are compared
+/// using an `IN` list of (row) values at runtime, which works for every database.
+pub fn create_by_pks_impl(
+    struct_name: &Ident,
+    ident_type: &Type,
+    fields_in_pk: &[&Field],
+    custom_identifier: bool,
+    dialect: Dialect,
+) -> TokenStream2 {
+    match fields_in_pk {
+        [field]
+            if dialect == Dialect::Postgres && !custom_identifier && is_array_type(&field.ty) =>
+        {
+            let condition = QueryBuilder::<Field>::new().build_where_any(field);
+
+            quote! {
+                impl sqlx_extension::sqlx_extension_core::traits::by_pks::ByPks<sqlx_extension::sqlx_extension_core::sqlx::Postgres> for #struct_name {
+                    fn chunk_size() -> usize {
+                        usize::MAX
+                    }
+
+                    fn by_pks_condition(_count: usize) -> String {
+                        #condition.into()
+                    }
+
+                    fn bind_pks<'q>(
+                        pks: &'q [Self::Identifier],
+                        arguments: &mut <sqlx_extension::sqlx_extension_core::sqlx::Postgres as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments,
+                    ) {
+                        let values = pks
+                            .iter()
+                            .map(|(value,)| ::std::clone::Clone::clone(value))
+                            .collect::<Vec<_>>();
+                        sqlx_extension::sqlx_extension_core::sqlx::Arguments::add(arguments, values);
+                    }
+                }
+            }
+        }
+        _ => {
+            let chunk_size = (dialect.max_placeholders() / fields_in_pk.len()).max(1);
+            let columns = create_columns(fields_in_pk, false);
+            let dialect = create_dialect(dialect);
+
+            quote! {
+                impl<DB> sqlx_extension::sqlx_extension_core::traits::by_pks::ByPks<DB> for #struct_name
+                where
+                    DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+                    for<'q> #ident_type: sqlx_extension::sqlx_extension_core::traits::bind_arguments::BindArguments<'q, DB>,
+                {
+                    fn chunk_size() -> usize {
+                        #chunk_size
+                    }
+
+                    fn by_pks_condition(count: usize) -> String {
+                        let prefix = "";
+                        let mut columns: Vec<sqlx_extension::sqlx_extension_core::statements::StatementColumn> = Vec::new();
+                        #(#columns)*
+
+                        let columns = columns.iter().collect::<Vec<_>>();
+                        sqlx_extension::sqlx_extension_core::query_builder::QueryBuilder::new()
+                            .dialect(#dialect)
+                            .build_where_in(&columns, count)
+                    }
+
+                    fn bind_pks<'q>(
+                        pks: &'q [Self::Identifier],
+                        arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments,
+                    ) {
+                        for pk in pks {
+                            sqlx_extension::sqlx_extension_core::traits::bind_arguments::BindArguments::<'q, DB>::bind_arguments(pk, arguments);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// This function returns whether the given type is one of `ARRAY_TYPES` (without any generic
+/// arguments).
+fn is_array_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.arguments.is_empty() && ARRAY_TYPES.iter().any(|t| s.ident == t)),
+        _ => false,
+    }
+}
@@ -0,0 +1,76 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::models::field::Field;
+use syn::{Ident, Visibility};
+
+/// This function creates a struct containing one field per primary key column, which is used
+/// as the identifier of the entity in case no `ident` attribute was given and the primary
+/// key consists of multiple fields.
+/// The struct can be decoded from a row (e.g. the one returned by `insert_without_pk_return_pk`),
+/// bound to the primary key placeholders and constructed from a tuple of all key fields.
+/// `FromRow` is implemented by hand rather than derived, as the expansion of the derive refers
+/// to `::sqlx`, which the crate deriving `Entity` doesn't necessarily depend on.
+pub fn create_key_struct(
+    struct_name: &Ident,
+    key_name: &Ident,
+    visibility: &Visibility,
+    fields_in_pk: &[(Visibility, Field)],
+) -> TokenStream2 {
+    let field_visibilities = fields_in_pk.iter().map(|(v, _)| v);
+    let idents = fields_in_pk
+        .iter()
+        .map(|(_, f)| &f.identifier)
+        .collect::<Vec<_>>();
+    let types = fields_in_pk.iter().map(|(_, f)| &f.ty).collect::<Vec<_>>();
+    let indices = (0..fields_in_pk.len()).map(syn::Index::from);
+
+    // the names of the columns as returned by the database
+    let names = fields_in_pk.iter().map(|(_, f)| f.name().into_owned());
+
+    let doc = format!(
+        "The primary key of `{}`, generated by the `Entity` derive macro.",
+        struct_name
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        #visibility struct #key_name {
+            #(#field_visibilities #idents: #types,)*
+        }
+
+        impl<'r, R> sqlx_extension::sqlx_extension_core::sqlx::FromRow<'r, R> for #key_name
+        where
+            R: sqlx_extension::sqlx_extension_core::sqlx::Row,
+            &'static str: sqlx_extension::sqlx_extension_core::sqlx::ColumnIndex<R>,
+            #(#types: sqlx_extension::sqlx_extension_core::sqlx::Decode<'r, R::Database>
+                + sqlx_extension::sqlx_extension_core::sqlx::Type<R::Database>,)*
+        {
+            fn from_row(row: &'r R) -> sqlx_extension::sqlx_extension_core::sqlx::Result<Self> {
+                Ok(Self {
+                    #(#idents: row.try_get(#names)?,)*
+                })
+            }
+        }
+
+        impl<'q, DB> sqlx_extension::sqlx_extension_core::traits::bind_arguments::BindArguments<'q, DB> for #key_name
+        where
+            DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+            #(#types: 'q + Sync
+                + sqlx_extension::sqlx_extension_core::sqlx::Encode<'q, DB>
+                + sqlx_extension::sqlx_extension_core::sqlx::Type<DB>,)*
+        {
+            fn bind_arguments(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(sqlx_extension::sqlx_extension_core::sqlx::Arguments::add(arguments, &self.#idents);)*
+            }
+        }
+
+        impl ::std::convert::From<(#(#types,)*)> for #key_name {
+            fn from(key: (#(#types,)*)) -> Self {
+                Self {
+                    #(#idents: key.#indices,)*
+                }
+            }
+        }
+    }
+}
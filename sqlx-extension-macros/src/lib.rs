@@ -6,7 +6,13 @@ use crate::sql_describe::sql_describe_macro;
 use entity::entity_macro;
 use proc_macro::TokenStream;
 
-#[proc_macro_derive(Describe, attributes(rename, p_key, table))]
+#[proc_macro_derive(
+    Describe,
+    attributes(
+        rename, p_key, tenant, generated, flatten, sql_type, json, encrypted, read_expr,
+        write_expr, table, schema, describe
+    )
+)]
 pub fn sql_describe(input: TokenStream) -> TokenStream {
     sql_describe_macro(input)
 }
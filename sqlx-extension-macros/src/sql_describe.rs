@@ -1,17 +1,42 @@
+mod bind;
+mod changeset;
+#[cfg(feature = "check")]
+mod check;
+mod columns;
+mod encoding;
+pub(crate) mod flatten;
+mod new_entity;
 mod queries;
+mod tenant;
 
 use proc_macro::TokenStream;
-use proc_macro2::{Span, TokenStream as TokenStream2};
-use quote::quote;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
 use sqlx_extension_core::{
-    models::{attribute::Attribute, field::Field},
-    query_builder::QueryBuilder,
+    dialect::Dialect,
+    models::{
+        attribute::{Attribute, DescribeOption},
+        field::Field,
+        rename_rule::RenameRule,
+    },
+    query_builder::{QueryBuilder, QueryColumn},
+    statements::Statements,
 };
-use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, Ident};
+use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, Ident, Meta, NestedMeta};
 
-use crate::utils::{parse_attributes, parse_field};
+use crate::utils::{parse_attributes, parse_dialect, parse_fields, parse_rename_rule};
 
-use self::queries::create_query_fn;
+use self::bind::create_bind_fields_impl;
+use self::changeset::create_changeset;
+use self::columns::{create_columns, create_describe_columns_impl};
+use self::encoding::create_has_encrypted_fields;
+use self::flatten::{
+    create_dialect, create_flatten_columns_impl, create_from_row_impls,
+    create_render_statements_impl,
+};
+use self::new_entity::create_new_entity;
+use self::queries::{create_query_fns, create_runtime_query_fns};
+use self::tenant::create_tenant_impl;
 
 /// This macro is used to generate an implementation of the describe
 /// trait.
@@ -25,37 +50,246 @@ pub fn sql_describe_macro(input: TokenStream) -> TokenStream {
         }) => {
             let struct_name = &input.ident;
 
-            let struct_attributes: Vec<_> = parse_attributes(input.attrs).collect();
+            // only the derives following this one are visible, hence a derive of `FromRow`
+            // preceding it merely results in conflicting implementations
+            let derives_from_row = derives_from_row(&input.attrs);
+            let struct_attributes = match parse_attributes(input.attrs) {
+                Ok(attributes) => attributes,
+                Err(e) => return e.to_compile_error().into(),
+            };
 
-            // parse name of table
-            let table_name = parse_table_name(struct_attributes.iter())
-                .expect("Table name was not provided! aborting...");
+            let rename_rule = parse_rename_rule(struct_attributes.iter());
 
-            // parse fields
-            let fields = fields_named
-                .named
-                .into_iter()
-                .filter_map(parse_field)
-                .collect::<Vec<_>>();
+            // parse name of table; in case it isn't provided, it is derived from the struct
+            // the deprecated form `schema.table` is split into the schema and the table
+            let (qualifying_schema, table_name) = match parse_table_name(struct_attributes.iter()) {
+                Some(name) => match name.split_once('.') {
+                    Some((schema, table)) => (Some(schema), table.to_string()),
+                    None => (None, name.to_string()),
+                },
+                None => (None, default_table_name(struct_name, rename_rule)),
+            };
+            let table_name = &table_name[..];
+            let schema_name = match (
+                parse_schema_name(struct_attributes.iter()),
+                qualifying_schema,
+            ) {
+                (Some(_), Some(_)) => return syn::Error::new_spanned(
+                    struct_name,
+                    "a table qualified by its schema can't be combined with the `schema` attribute",
+                )
+                .to_compile_error()
+                .into(),
+                (schema_name, qualifying_schema) => schema_name.or(qualifying_schema),
+            };
+            let deprecations = qualifying_schema.map(|_| {
+                quote_spanned! {struct_name.span()=>
+                    const _: () = sqlx_extension::sqlx_extension_core::dialect::SCHEMA_QUALIFIED_TABLE;
+                }
+            });
+            let dialect = parse_dialect(struct_attributes.iter());
+
+            // parse fields (alongside their visibility)
+            let (visibilities, fields): (Vec<_>, Vec<_>) =
+                match parse_fields(fields_named.named, rename_rule) {
+                    Ok(fields) => fields.into_iter().unzip(),
+                    Err(e) => return e.to_compile_error().into(),
+                };
 
             // create version which doesn't own the fields
             let fields_ref = fields.iter().collect::<Vec<_>>();
+            let tenant_field = match parse_tenant_field(&fields_ref, dialect) {
+                Ok(tenant_field) => tenant_field,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            let flattened = match has_flattened_fields(&fields_ref) {
+                Ok(flattened) => flattened,
+                Err(e) => return e.to_compile_error().into(),
+            };
+            if let Err(e) = validate_encrypted_fields(&fields_ref) {
+                return e.to_compile_error().into();
+            }
+
+            // the fields which correspond to a single column
+            let column_fields = fields_ref
+                .iter()
+                .copied()
+                .filter(|f| f.flatten_prefix().is_none())
+                .collect::<Vec<_>>();
+
+            // verify the table and columns against the database schema (if requested);
+            // the errors are emitted alongside the implementation to avoid follow-up errors
+            let schema_errors =
+                if has_describe_option(struct_attributes.iter(), &DescribeOption::Check) {
+                    verify_schema(struct_name, schema_name, table_name, &column_fields)
+                } else {
+                    TokenStream2::new()
+                };
 
-            // create all functions
-            let mut queries = Vec::new();
-            create_queries(&mut queries, &fields_ref, &table_name);
+            // create the builder rendering the statements for the table
+            let mut builder = QueryBuilder::new().table_name(table_name).dialect(dialect);
+            if let Some(schema_name) = schema_name {
+                builder = builder.schema_name(schema_name);
+            }
+            if let Some(tenant_field) = tenant_field {
+                builder = builder.tenant_field(tenant_field);
+            }
+            let qualified_table_name = builder.qualified_table_name().unwrap_or_default();
+            let quoted_table_name = dialect.quote_identifier(table_name);
+
+            // create all functions; in case there are flattened fields, the statements can
+            // only be rendered at runtime (once, into a static cell)
+            let (queries, statements_cell) = if flattened {
+                let statements_cell = quote! {
+                    static STATEMENTS: sqlx_extension::sqlx_extension_core::statements::StatementsCell =
+                        sqlx_extension::sqlx_extension_core::statements::StatementsCell::new();
+                };
+                (create_runtime_query_fns(struct_name), Some(statements_cell))
+            } else {
+                (
+                    create_query_fns(&Statements::render(builder, &fields_ref)),
+                    None,
+                )
+            };
+
+            // create the binding of the fields as well as the column constants
+            // the generated fields are neither inserted by `insert_without_pk` nor updated
+            let (fields_not_in_pk, fields_in_pk) = split_fields_by_pk(&fields_ref);
+            let fields_not_in_pk = fields_not_in_pk
+                .into_iter()
+                .filter(|f| !f.is_generated())
+                .collect::<Vec<_>>();
+            let bind_fields =
+                create_bind_fields_impl(struct_name, &fields_ref, &fields_not_in_pk, &fields_in_pk);
+            // encrypted columns can't be compared, so no filters can be created for them
+            let filterable_fields = column_fields
+                .iter()
+                .copied()
+                .filter(|f| !f.is_encrypted())
+                .collect::<Vec<_>>();
+            let columns = create_columns(struct_name, &filterable_fields, dialect);
+            let describe_columns = create_describe_columns_impl(struct_name, &fields_ref);
+            let flatten_columns = create_flatten_columns_impl(struct_name, &fields_ref);
+            let implements_from_row =
+                has_describe_option(struct_attributes.iter(), &DescribeOption::FromRow);
+            let from_row = create_from_row_impls(struct_name, &fields_ref, implements_from_row);
+            let from_row_errors = verify_from_row(
+                struct_name,
+                &fields_ref,
+                implements_from_row,
+                derives_from_row,
+            );
+            let tenant = create_tenant_impl(struct_name, tenant_field);
+            let visible_fields = visibilities.iter().zip(fields.iter()).collect::<Vec<_>>();
+            // changesets and new entities return the entity, which requires `RETURNING`
+            let returning = dialect.supports_returning();
+            let changeset =
+                if has_describe_option(struct_attributes.iter(), &DescribeOption::Changeset) {
+                    if !returning {
+                        return syn::Error::new_spanned(
+                            struct_name,
+                            "changesets require a dialect supporting `RETURNING`",
+                        )
+                        .to_compile_error()
+                        .into();
+                    }
+                    create_changeset(
+                        struct_name,
+                        &input.vis,
+                        &visible_fields,
+                        table_name,
+                        schema_name,
+                        dialect,
+                    )
+                } else {
+                    TokenStream2::new()
+                };
+            let new_entity = if has_describe_option(struct_attributes.iter(), &DescribeOption::New)
+            {
+                if !returning {
+                    return syn::Error::new_spanned(
+                        struct_name,
+                        "new entities require a dialect supporting `RETURNING`",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+                create_new_entity(struct_name, &input.vis, &visible_fields)
+            } else {
+                TokenStream2::new()
+            };
+            let catalog_entry =
+                if has_describe_option(struct_attributes.iter(), &DescribeOption::NoCatalog) {
+                    TokenStream2::new()
+                } else {
+                    create_catalog_entry(struct_name, dialect)
+                };
+            let dialect_tokens = create_dialect(dialect);
+            let postgres_dialect = create_postgres_dialect_impl(struct_name, dialect);
+            let has_encrypted_fields = create_has_encrypted_fields(&fields_ref);
+            let render_statements = create_render_statements_impl(
+                struct_name,
+                &fields_ref,
+                table_name,
+                schema_name,
+                dialect,
+            );
 
             // create trait implementation by interpolating all queries
             Some(
                 quote! {
-                    impl sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe for #struct_name {
-                        #(#queries)*
+                    const _: () = {
+                        #statements_cell
+
+                        impl sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe for #struct_name {
+                            #(#queries)*
+
+                            /// The name of the table for the current entity.
+                            fn table_name() -> &'static str {
+                                #qualified_table_name
+                            }
 
-                        /// The name of the table for the current entity.
-                        fn table_name() -> &'static str {
-                            #table_name
+                            /// The name of the table for the current entity, without its schema.
+                            fn table_name_without_schema() -> &'static str {
+                                #quoted_table_name
+                            }
+
+                            /// The dialect the statements are rendered for.
+                            fn dialect() -> sqlx_extension::sqlx_extension_core::dialect::Dialect {
+                                #dialect_tokens
+                            }
+
+                            #has_encrypted_fields
                         }
-                    }
+                    };
+
+                    #postgres_dialect
+
+                    #render_statements
+
+                    #bind_fields
+
+                    #columns
+
+                    #describe_columns
+
+                    #flatten_columns
+
+                    #from_row
+
+                    #tenant
+
+                    #changeset
+
+                    #new_entity
+
+                    #catalog_entry
+
+                    #schema_errors
+
+                    #from_row_errors
+
+                    #deprecations
                 }
                 .into(),
             )
@@ -66,6 +300,35 @@ pub fn sql_describe_macro(input: TokenStream) -> TokenStream {
     res.unwrap_or_default()
 }
 
+/// This function implements `PostgresDialect` in case the statements are rendered for
+/// Postgres, which the functions binding filters require.
+fn create_postgres_dialect_impl(struct_name: &Ident, dialect: Dialect) -> TokenStream2 {
+    if dialect != Dialect::Postgres {
+        return quote!();
+    }
+
+    quote! {
+        impl sqlx_extension::sqlx_extension_core::traits::postgres_dialect::PostgresDialect for #struct_name {}
+    }
+}
+
+/// This function registers the statements of the struct within the statement catalog.
+fn create_catalog_entry(struct_name: &Ident, dialect: Dialect) -> TokenStream2 {
+    let name = struct_name.to_string();
+    let dialect = create_dialect(dialect);
+
+    quote! {
+        sqlx_extension::sqlx_extension_core::inventory::submit! {
+            sqlx_extension::sqlx_extension_core::catalog::CatalogEntry::new(
+                concat!(module_path!(), "::", #name),
+                #dialect,
+                <#struct_name as sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe>::table_name,
+                <#struct_name as sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe>::statements,
+            )
+        }
+    }
+}
+
 /// This function parses the name of a table from the attributes of a struct.
 fn parse_table_name<'a>(attrs: impl IntoIterator<Item = &'a Attribute> + 'a) -> Option<&'a str> {
     attrs.into_iter().find_map(|a| {
@@ -77,81 +340,214 @@ fn parse_table_name<'a>(attrs: impl IntoIterator<Item = &'a Attribute> + 'a) ->
     })
 }
 
-/// This method fills the given array with all queries for a given object.
-fn create_queries(all_queries: &mut Vec<TokenStream2>, all_fields: &[&Field], table_name: &str) {
-    // split the fields into contained / not contained in primary key
-    let (fields_not_in_pk, fields_in_pk): (Vec<&Field>, Vec<&Field>) =
-        all_fields
-            .iter()
-            .fold((Vec::new(), Vec::new()), |(mut not_pk, mut pk), curr| {
-                // check whether the current field is marked as primary key
-                let is_pk = curr.attributes.contains(&Attribute::PrimaryKey);
-
-                // yes --> add it to the vector of pk-fields
-                if is_pk {
-                    pk.push(curr);
-                } else {
-                    // nope --> add it to the vector of non-pks
-                    not_pk.push(curr);
-                }
+/// This function parses the name of the schema from the attributes of a struct (if any).
+fn parse_schema_name<'a>(attrs: impl IntoIterator<Item = &'a Attribute> + 'a) -> Option<&'a str> {
+    attrs.into_iter().find_map(|a| {
+        if let Attribute::Schema { schema_name } = a {
+            Some(&schema_name[..])
+        } else {
+            None
+        }
+    })
+}
 
-                (not_pk, pk)
-            });
+/// This function derives the name of the table from the name of the struct: the rename
+/// rule is applied if there is one, else it is converted to snake case.
+fn default_table_name(struct_name: &Ident, rename_rule: Option<RenameRule>) -> String {
+    rename_rule
+        .unwrap_or(RenameRule::SnakeCase)
+        .apply(&struct_name.to_string())
+}
 
-    // create query builder
-    let builder = QueryBuilder::new()
-        .table_name(table_name)
-        .entity_attributes(all_fields);
-
-    // create queries
-    let insert = create_query_fn(
-        &create_ident("insert"),
-        &builder.build_insert(&[]).unwrap_or_default(),
-    );
-    all_queries.push(insert);
-
-    // make sure to change entity attributes to be just the ones which
-    // are not in the PK
-    let builder = builder.entity_attributes(&fields_not_in_pk);
-
-    let insert_without_pk = create_query_fn(
-        &create_ident("insert_without_pk"),
-        &builder.build_insert(&all_fields).unwrap_or_default(),
-    );
-    all_queries.push(insert_without_pk);
-
-    let insert_without_pk_return_pk = create_query_fn(
-        &create_ident("insert_without_pk_return_pk"),
-        &builder.build_insert(&fields_in_pk).unwrap_or_default(),
-    );
-    all_queries.push(insert_without_pk_return_pk);
-
-    // retrieve queries
-    let retrieve_all = create_query_fn(
-        &create_ident("retrieve_all"),
-        &builder.build_retrieve().unwrap_or_default(),
-    );
-    all_queries.push(retrieve_all);
-
-    let retrieve_by_pk = create_query_fn(
-        &create_ident("retrieve_by_pk"),
-        &builder
-            .build_retrieve_where(&fields_in_pk)
-            .unwrap_or_default(),
-    );
-    all_queries.push(retrieve_by_pk);
-
-    // delete queries
-    let delete_by_pk = create_query_fn(
-        &create_ident("delete_by_pk"),
-        &builder
-            .build_delete_where(&fields_in_pk)
-            .unwrap_or_default(),
-    );
-    all_queries.push(delete_by_pk);
+/// This function checks whether any field is flattened. Flattened fields can neither be
+/// part of the primary key, contain the tenant nor be stored as JSON or encrypted.
+fn has_flattened_fields(all_fields: &[&Field]) -> syn::Result<bool> {
+    let mut flattened = all_fields.iter().filter(|f| f.flatten_prefix().is_some());
+    if let Some(field) = flattened
+        .clone()
+        .find(|f| f.is_primary_key() || f.is_tenant())
+    {
+        return Err(syn::Error::new_spanned(
+            &field.identifier,
+            "a flattened field can neither be annotated with `p_key` nor `tenant`",
+        ));
+    }
+    if let Some(field) = flattened
+        .clone()
+        .find(|f| f.is_json() || f.is_encrypted() || f.sql_type().is_some())
+    {
+        return Err(syn::Error::new_spanned(
+            &field.identifier,
+            "a flattened field can neither be annotated with `json`, `encrypted` nor `sql_type`",
+        ));
+    }
+
+    Ok(flattened.next().is_some())
 }
 
-/// This helper function just creates a ident with the span `call_site`.
-fn create_ident(name: &str) -> Ident {
-    Ident::new(name, Span::call_site())
+/// This function verifies the fields annotated with `encrypted`: as every encryption yields a
+/// different ciphertext, they can't be compared and hence can neither be part of the primary
+/// key nor contain the tenant.
+fn validate_encrypted_fields(all_fields: &[&Field]) -> syn::Result<()> {
+    for field in all_fields.iter().filter(|f| f.is_encrypted()) {
+        if field.is_primary_key() || field.is_tenant() {
+            return Err(syn::Error::new_spanned(
+                &field.identifier,
+                "an encrypted field can neither be annotated with `p_key` nor `tenant`",
+            ));
+        }
+        if field.is_json() {
+            return Err(syn::Error::new_spanned(
+                &field.identifier,
+                "a field can't be annotated with both `encrypted` and `json`",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// This function returns the field annotated with `tenant` (if any). As the tenant is bound
+/// to the first placeholder, it requires a dialect with numbered placeholders.
+fn parse_tenant_field<'a>(
+    all_fields: &[&'a Field],
+    dialect: Dialect,
+) -> syn::Result<Option<&'a Field>> {
+    let mut tenant_fields = all_fields.iter().copied().filter(|f| f.is_tenant());
+    let tenant_field = match tenant_fields.next() {
+        Some(tenant_field) => tenant_field,
+        None => return Ok(None),
+    };
+
+    if let Some(field) = tenant_fields.next() {
+        return Err(syn::Error::new_spanned(
+            &field.identifier,
+            "only a single field can be annotated with `tenant`",
+        ));
+    }
+    if dialect == Dialect::MySql {
+        return Err(syn::Error::new_spanned(
+            &tenant_field.identifier,
+            "the `tenant` attribute isn't supported by the mysql dialect",
+        ));
+    }
+
+    Ok(Some(tenant_field))
+}
+
+/// This function checks whether the `describe` attribute of a struct contains the given option.
+fn has_describe_option<'a>(
+    attrs: impl IntoIterator<Item = &'a Attribute> + 'a,
+    option: &DescribeOption,
+) -> bool {
+    attrs.into_iter().any(|a| {
+        if let Attribute::Describe { options } = a {
+            options.contains(option)
+        } else {
+            false
+        }
+    })
+}
+
+/// This function checks whether the given attributes derive `FromRow` (e.g. `sqlx::FromRow`).
+fn derives_from_row(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("derive"))
+        .filter_map(|a| a.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path
+                    .segments
+                    .last()
+                    .map(|s| s.ident == "FromRow")
+                    .unwrap_or(false),
+                _ => false,
+            }),
+            _ => false,
+        })
+}
+
+/// This function verifies that the implementation of `FromRow` is requested (using
+/// `#[describe(from_row)]`) in case the derive of sqlx can't decode the struct, and that it
+/// doesn't conflict with a derive of `FromRow`, returning the errors as tokens.
+fn verify_from_row(
+    struct_name: &Ident,
+    all_fields: &[&Field],
+    implements_from_row: bool,
+    derives_from_row: bool,
+) -> TokenStream2 {
+    let requires_from_row = all_fields
+        .iter()
+        .any(|f| f.flatten_prefix().is_some() || f.is_json() || f.is_encrypted());
+
+    let message = if implements_from_row && derives_from_row {
+        format!(
+            "`{}` implements `FromRow` due to `#[describe(from_row)]`, so it must not derive \
+             `FromRow` as well",
+            struct_name
+        )
+    } else if requires_from_row && !implements_from_row {
+        format!(
+            "`{}` has flattened, JSON or encrypted fields, which the derive of `sqlx::FromRow` \
+             can't decode: add `#[describe(from_row)]` to implement `FromRow` using `Describe` \
+             instead of deriving it",
+            struct_name
+        )
+    } else {
+        return TokenStream2::new();
+    };
+
+    syn::Error::new_spanned(struct_name, message).to_compile_error()
+}
+
+/// This function verifies the table and columns against the database schema, returning
+/// the errors as tokens.
+#[cfg(feature = "check")]
+fn verify_schema(
+    struct_name: &Ident,
+    schema_name: Option<&str>,
+    table_name: &str,
+    fields: &[&Field],
+) -> TokenStream2 {
+    check::check_schema(struct_name, schema_name, table_name, fields)
+        .err()
+        .map(|e| e.to_compile_error())
+        .unwrap_or_default()
+}
+
+/// Without the `check` feature, the schema can't be verified, so requesting it is an error.
+#[cfg(not(feature = "check"))]
+fn verify_schema(
+    struct_name: &Ident,
+    _schema_name: Option<&str>,
+    _table_name: &str,
+    _fields: &[&Field],
+) -> TokenStream2 {
+    syn::Error::new_spanned(
+        struct_name,
+        "`#[describe(check)]` requires the `check` feature of `sqlx-extension`",
+    )
+    .to_compile_error()
+}
+
+/// This function splits the fields into the ones not contained / contained in the
+/// primary key.
+fn split_fields_by_pk<'a>(all_fields: &[&'a Field]) -> (Vec<&'a Field>, Vec<&'a Field>) {
+    all_fields
+        .iter()
+        .fold((Vec::new(), Vec::new()), |(mut not_pk, mut pk), curr| {
+            // check whether the current field is marked as primary key
+            let is_pk = curr.attributes.contains(&Attribute::PrimaryKey);
+
+            // yes --> add it to the vector of pk-fields
+            if is_pk {
+                pk.push(curr);
+            } else {
+                // nope --> add it to the vector of non-pks
+                not_pk.push(curr);
+            }
+
+            (not_pk, pk)
+        })
 }
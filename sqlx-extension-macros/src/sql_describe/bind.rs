@@ -0,0 +1,103 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::{models::field::Field, query_builder::QueryColumn};
+use syn::Ident;
+
+use super::encoding::{encoded_type, encoded_value, encryptable_bound};
+
+/// This function creates the implementation of `BindFields` for the given struct, binding
+/// the fields in the same order as they appear within the generated statements.
+pub fn create_bind_fields_impl(
+    struct_name: &Ident,
+    all_fields: &[&Field],
+    fields_not_in_pk: &[&Field],
+    fields_in_pk: &[&Field],
+) -> TokenStream2 {
+    let bounds = all_fields.iter().map(|f| create_bind_bound(f));
+
+    // the tenant is bound to the first placeholder by `TenantScoped`, so it is neither part
+    // of the `SET` part nor the conditions of the primary key
+    let set_fields = fields_not_in_pk
+        .iter()
+        .copied()
+        .filter(|f| !f.is_tenant())
+        .collect::<Vec<_>>();
+    let pk_conditions = fields_in_pk
+        .iter()
+        .copied()
+        .filter(|f| !f.is_tenant())
+        .collect::<Vec<_>>();
+
+    let bind_all = create_bind_statements(all_fields);
+    let bind_without_pk = create_bind_statements(fields_not_in_pk);
+    let bind_pk = create_bind_statements(&pk_conditions);
+    let bind_set = create_bind_statements(&set_fields);
+
+    quote! {
+        impl<'q, DB> sqlx_extension::sqlx_extension_core::traits::bind_fields::BindFields<'q, DB> for #struct_name
+        where
+            DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+            #(#bounds,)*
+        {
+            fn bind_all(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(#bind_all)*
+            }
+
+            fn bind_without_pk(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(#bind_without_pk)*
+            }
+
+            fn bind_pk(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(#bind_pk)*
+            }
+
+            fn bind_set(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(#bind_set)*
+            }
+        }
+    }
+}
+
+/// This function creates the bound required to bind the given field to the arguments of
+/// the database `DB` (for the lifetime `'q`).
+pub fn create_bind_bound(field: &Field) -> TokenStream2 {
+    let ty = &field.ty;
+    if field.flatten_prefix().is_some() {
+        quote! {
+            #ty: sqlx_extension::sqlx_extension_core::traits::bind_fields::BindFields<'q, DB>
+        }
+    } else {
+        let encoded = encoded_type(field);
+        let encryptable = encryptable_bound(field).map(|bound| quote!(#bound,));
+        quote! {
+            #ty: 'q + Sync,
+            #encryptable
+            #encoded: sqlx_extension::sqlx_extension_core::sqlx::Encode<'q, DB>
+                + sqlx_extension::sqlx_extension_core::sqlx::Type<DB>
+        }
+    }
+}
+
+/// This function creates a statement adding the given value (a reference to the value of
+/// the field) to the arguments; flattened fields add all of their columns, while fields
+/// stored as JSON are wrapped and encrypted fields are encrypted.
+pub fn create_bind_statement(field: &Field, value: TokenStream2) -> TokenStream2 {
+    if field.flatten_prefix().is_some() {
+        quote! {
+            sqlx_extension::sqlx_extension_core::traits::bind_fields::BindFields::<'q, DB>::bind_all(#value, arguments);
+        }
+    } else {
+        let value = encoded_value(field, value);
+        quote! {
+            sqlx_extension::sqlx_extension_core::sqlx::Arguments::add(arguments, #value);
+        }
+    }
+}
+
+/// This function creates a statement adding each of the given fields to the arguments.
+fn create_bind_statements<'a>(fields: &'a [&'a Field]) -> impl Iterator<Item = TokenStream2> + 'a {
+    fields.iter().map(|f| {
+        let ident = &f.identifier;
+        create_bind_statement(f, quote!(&self.#ident))
+    })
+}
@@ -0,0 +1,198 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use sqlx_extension_core::{dialect::Dialect, models::field::Field, query_builder::QueryColumn};
+use syn::{Ident, Visibility};
+
+use super::bind::{create_bind_bound, create_bind_statement};
+use super::flatten::{create_builder, create_columns};
+
+/// This function creates the changeset of the given struct (e.g. `UserChangeset`), which
+/// contains every field neither annotated with `p_key`, `tenant` nor `generated` as `Option`,
+/// alongside the implementations of `Changeset` and `BindChangeset`. Additionally,
+/// `TrackChanges` is implemented for the struct, which derives the changeset from the flags
+/// of the changed fields, each described by an associated `TrackedField` constant of the
+/// changeset (e.g. `UserChangeset::EMAIL`).
+/// As the present fields are only known at runtime, so is the update statement. The
+/// changeset is only generated for structs annotated with `#[describe(changeset)]`, which
+/// have to contain at least one field annotated with `p_key`.
+pub fn create_changeset(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    fields: &[(&Visibility, &Field)],
+    table_name: &str,
+    schema_name: Option<&str>,
+    dialect: Dialect,
+) -> TokenStream2 {
+    let changeset_name = format_ident!("{}Changeset", struct_name);
+    if !fields.iter().any(|(_, f)| f.is_primary_key()) {
+        return syn::Error::new_spanned(
+            struct_name,
+            "`#[describe(changeset)]` requires at least one field annotated with `p_key`",
+        )
+        .to_compile_error();
+    }
+    // generated primary keys still identify the row, but generated fields are never changed
+    let (key_fields, changed_fields): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .partition(|(_, f)| f.is_primary_key() || f.is_tenant());
+    let key_fields: Vec<&Field> = key_fields.into_iter().map(|&(_, f)| f).collect();
+    let changed_fields: Vec<_> = changed_fields
+        .into_iter()
+        .filter(|(_, f)| !f.is_generated())
+        .collect();
+
+    let field_visibilities = changed_fields.iter().map(|(v, _)| v).collect::<Vec<_>>();
+    let changed_fields: Vec<&Field> = changed_fields.iter().map(|(_, f)| *f).collect();
+    let idents = changed_fields
+        .iter()
+        .map(|f| &f.identifier)
+        .collect::<Vec<_>>();
+    let types = changed_fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let names = idents
+        .iter()
+        .map(|i| i.to_string().trim_start_matches("r#").to_string())
+        .collect::<Vec<_>>();
+    let const_names = names
+        .iter()
+        .map(|name| format_ident!("{}", name.to_uppercase()));
+    let const_docs = names
+        .iter()
+        .map(|name| format!("The field `{}`, changed through `Tracked`.", name));
+    let indices = 0..changed_fields.len();
+
+    // the columns of the present fields as well as the ones of the primary key
+    let set_columns = create_columns(&changed_fields, false);
+    let key_columns = create_columns(&key_fields, true);
+    let builder = create_builder(table_name, schema_name, dialect);
+
+    let bounds = changed_fields.iter().map(|f| create_bind_bound(f));
+    let bind_statements = changed_fields
+        .iter()
+        .map(|f| create_bind_statement(f, quote!(value)));
+
+    let doc = format!(
+        "The changeset of `{}`, generated by the `Describe` derive macro: every present \
+         field is updated by `Changeset::apply`.",
+        struct_name
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Default)]
+        #visibility struct #changeset_name {
+            #(#field_visibilities #idents: Option<#types>,)*
+        }
+
+        impl #changeset_name {
+            #(
+                #[doc = #const_docs]
+                #field_visibilities const #const_names: sqlx_extension::sqlx_extension_core::traits::track_changes::TrackedField<#struct_name, #types> =
+                    sqlx_extension::sqlx_extension_core::traits::track_changes::TrackedField::new(#indices, #names, |e| &mut e.#idents);
+            )*
+        }
+
+        impl sqlx_extension::sqlx_extension_core::traits::changeset::Changeset for #changeset_name {
+            type Entity = #struct_name;
+
+            fn update_by_pk_returning(&self) -> Option<&'static str> {
+                let prefix = "";
+
+                // the columns of all present fields
+                let mut columns = Vec::new();
+                #(
+                    if self.#idents.is_some() {
+                        #set_columns
+                    }
+                )*
+                if columns.is_empty() {
+                    return None;
+                }
+                let set_columns = columns;
+
+                // the columns of the primary key and the tenant
+                let mut columns: Vec<sqlx_extension::sqlx_extension_core::statements::StatementColumn> = Vec::new();
+                #(#key_columns)*
+
+                let schema_name = None;
+                let builder = #builder;
+                let set_columns = set_columns.iter().collect::<Vec<_>>();
+                let pk_columns = columns
+                    .iter()
+                    .filter(|c| c.primary_key && !c.tenant)
+                    .collect::<Vec<_>>();
+
+                builder
+                    .build_update_returning(
+                        &set_columns,
+                        &pk_columns,
+                        <#struct_name as sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe>::select_list(),
+                    )
+                    .map(sqlx_extension::sqlx_extension_core::statements::intern)
+            }
+
+            fn update_without_where(&self) -> Option<&'static str> {
+                let prefix = "";
+
+                // the columns of all present fields
+                let mut columns: Vec<sqlx_extension::sqlx_extension_core::statements::StatementColumn> = Vec::new();
+                #(
+                    if self.#idents.is_some() {
+                        #set_columns
+                    }
+                )*
+
+                let schema_name = None;
+                let builder = #builder;
+                let set_columns = columns.iter().collect::<Vec<_>>();
+                builder
+                    .build_update_without_where(&set_columns)
+                    .map(sqlx_extension::sqlx_extension_core::statements::intern)
+            }
+
+            fn placeholder_count(&self) -> usize {
+                let prefix = "";
+
+                let mut columns: Vec<sqlx_extension::sqlx_extension_core::statements::StatementColumn> = Vec::new();
+                #(
+                    if self.#idents.is_some() {
+                        #set_columns
+                    }
+                )*
+                columns.len()
+            }
+        }
+
+        impl sqlx_extension::sqlx_extension_core::traits::track_changes::TrackChanges for #struct_name {
+            type Changeset = #changeset_name;
+
+            const TRACKED_FIELDS: &'static [&'static str] = &[#(#names),*];
+
+            fn changes(&self, dirty: &[bool]) -> Self::Changeset {
+                let mut dirty = dirty.iter().copied();
+                #changeset_name {
+                    #(
+                        #idents: if dirty.next().unwrap_or(false) {
+                            Some(self.#idents.clone())
+                        } else {
+                            None
+                        },
+                    )*
+                }
+            }
+        }
+
+        impl<'q, DB> sqlx_extension::sqlx_extension_core::traits::changeset::BindChangeset<'q, DB> for #changeset_name
+        where
+            DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+            #(#bounds,)*
+        {
+            fn bind_changes(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(
+                    if let Some(value) = &self.#idents {
+                        #bind_statements
+                    }
+                )*
+            }
+        }
+    }
+}
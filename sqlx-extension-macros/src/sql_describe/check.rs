@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use sqlx_extension_core::{models::field::Field, schema::SchemaSnapshot, sqlx::PgPool};
+use syn::{Error, Ident};
+
+/// A snapshot which was loaded, or the reason why it couldn't be loaded.
+type LoadedSnapshot = Result<SchemaSnapshot, String>;
+
+/// All snapshots loaded so far, keyed by their source (the database url or the file path).
+static SNAPSHOTS: Lazy<Mutex<HashMap<String, LoadedSnapshot>>> = Lazy::new(Default::default);
+
+/// This function verifies that the table as well as the columns of all fields used within
+/// the generated statements exist in the database schema.
+/// The schema is fetched from the database behind `DATABASE_URL`; in case it isn't set or
+/// `SQLX_OFFLINE` is enabled, the snapshot file within the manifest directory is used instead.
+pub fn check_schema(
+    struct_name: &Ident,
+    schema_name: Option<&str>,
+    table_name: &str,
+    fields: &[&Field],
+) -> Result<(), Error> {
+    let snapshot = load_snapshot().map_err(|e| Error::new(struct_name.span(), e))?;
+
+    let table = match schema_name {
+        Some(schema_name) => format!("{}.{}", schema_name, table_name),
+        None => table_name.into(),
+    };
+    let columns = snapshot.columns(&table).ok_or_else(|| {
+        Error::new(
+            struct_name.span(),
+            format!("table `{}` does not exist in the database schema", table),
+        )
+    })?;
+
+    // collect an error for every column which doesn't exist
+    fields
+        .iter()
+        .filter_map(|f| {
+            let column = f.name();
+            if columns.iter().any(|c| *c == column) {
+                None
+            } else {
+                Some(Error::new(
+                    f.identifier.span(),
+                    format!("column `{}` does not exist in table `{}`", column, table),
+                ))
+            }
+        })
+        .reduce(|mut errors, error| {
+            errors.combine(error);
+            errors
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// This function loads the snapshot of the database schema (once per source).
+fn load_snapshot() -> LoadedSnapshot {
+    let offline = env::var("SQLX_OFFLINE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    let (source, load): (String, fn(&str) -> LoadedSnapshot) = match env::var("DATABASE_URL") {
+        Ok(url) if !offline => (url, fetch_snapshot),
+        _ => {
+            let path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default())
+                .join(SchemaSnapshot::FILE_NAME);
+            (path.to_string_lossy().into_owned(), read_snapshot)
+        }
+    };
+
+    let mut snapshots = SNAPSHOTS.lock().unwrap_or_else(|e| e.into_inner());
+    snapshots
+        .entry(source)
+        .or_insert_with_key(|source| load(source))
+        .clone()
+}
+
+/// This function fetches the snapshot from the database behind the given url.
+fn fetch_snapshot(url: &str) -> LoadedSnapshot {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    runtime
+        .block_on(async {
+            let pool = PgPool::connect(url).await?;
+            let snapshot = SchemaSnapshot::fetch(&pool).await;
+            pool.close().await;
+            snapshot
+        })
+        .map_err(|e| format!("failed to fetch the schema from `DATABASE_URL`: {}", e))
+}
+
+/// This function reads the snapshot from the given file.
+fn read_snapshot(path: &str) -> LoadedSnapshot {
+    SchemaSnapshot::read(path)
+        .map_err(|e| format!("failed to read the schema snapshot `{}`: {}", path, e))
+}
@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use sqlx_extension_core::{dialect::Dialect, models::field::Field};
+use syn::{Ident, Type};
+
+use super::encoding::decoded_type;
+
+/// This function creates an associated `Column` constant for every field of the struct,
+/// named after the field in upper case (e.g. `User::EMAIL`). The name of each column is
+/// quoted according to the dialect, as it is interpolated into statements, while its label
+/// is the unquoted name.
+pub fn create_columns(
+    struct_name: &Ident,
+    all_fields: &[&Field],
+    dialect: Dialect,
+) -> TokenStream2 {
+    let columns = all_fields.iter().map(|f| {
+        let ident = &f.identifier;
+        let ty = &f.ty;
+        let name = f.name();
+        let quoted_name = dialect.quote_identifier(&name);
+        let const_name = format_ident!(
+            "{}",
+            ident.to_string().trim_start_matches("r#").to_uppercase()
+        );
+        let doc = format!("The column `{}`.", name);
+        let sql_type = f.sql_type().map(|sql_type| quote!(.typed(#sql_type)));
+
+        quote! {
+            #[doc = #doc]
+            pub const #const_name: sqlx_extension::sqlx_extension_core::column::Column<Self, #ty> =
+                sqlx_extension::sqlx_extension_core::column::Column::<Self, #ty>::new(#quoted_name, |e| &e.#ident)
+                    .labeled(#name)
+                    #sql_type;
+        }
+    });
+
+    quote! {
+        impl #struct_name {
+            #(#columns)*
+        }
+    }
+}
+
+/// This function creates the implementation of `DescribeColumns`, describing the rust type of
+/// every field (flattened fields describe all of their columns, fields stored as JSON are
+/// described as `Json`).
+pub fn create_describe_columns_impl(struct_name: &Ident, all_fields: &[&Field]) -> TokenStream2 {
+    let bounds = all_fields.iter().map(|f| {
+        let ty = &f.ty;
+        if f.flatten_prefix().is_some() {
+            quote! { #ty: sqlx_extension::sqlx_extension_core::traits::describe_columns::DescribeColumns<DB> }
+        } else {
+            let decoded = decoded_type(f);
+            quote! { #decoded: sqlx_extension::sqlx_extension_core::sqlx::Type<DB> }
+        }
+    });
+
+    let column_types = all_fields.iter().map(|f| {
+        let ty = &f.ty;
+
+        if let Some(prefix) = f.flatten_prefix() {
+            return quote! {
+                column_types.extend(
+                    <#ty as sqlx_extension::sqlx_extension_core::traits::describe_columns::DescribeColumns<DB>>::column_types()
+                        .into_iter()
+                        .map(|mut column_type| {
+                            column_type.name = format!("{}{}", #prefix, column_type.name).into();
+                            column_type
+                        })
+                );
+            };
+        }
+
+        let name = f.name();
+        let rust_type = quote!(#ty).to_string().replace(' ', "");
+        let nullable = is_option(ty);
+        let decoded = decoded_type(f);
+
+        quote! {
+            column_types.push(sqlx_extension::sqlx_extension_core::traits::describe_columns::ColumnType {
+                name: ::std::borrow::Cow::Borrowed(#name),
+                rust_type: #rust_type,
+                nullable: #nullable,
+                type_info: <#decoded as sqlx_extension::sqlx_extension_core::sqlx::Type<DB>>::type_info,
+                compatible: <#decoded as sqlx_extension::sqlx_extension_core::sqlx::Type<DB>>::compatible,
+            });
+        }
+    });
+
+    quote! {
+        impl<DB> sqlx_extension::sqlx_extension_core::traits::describe_columns::DescribeColumns<DB> for #struct_name
+        where
+            DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+            #(#bounds,)*
+        {
+            fn column_types() -> Vec<sqlx_extension::sqlx_extension_core::traits::describe_columns::ColumnType<DB>> {
+                let mut column_types = Vec::new();
+                #(#column_types)*
+                column_types
+            }
+        }
+    }
+}
+
+/// This function checks whether the given type is an `Option`.
+fn is_option(ty: &Type) -> bool {
+    if let Type::Path(path) = ty {
+        path.path
+            .segments
+            .last()
+            .map(|s| s.ident == "Option")
+            .unwrap_or(false)
+    } else {
+        false
+    }
+}
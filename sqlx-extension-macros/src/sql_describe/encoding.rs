@@ -0,0 +1,148 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::models::field::Field;
+use syn::{GenericArgument, PathArguments, Type};
+
+/// This function returns the type which is bound to the arguments for the given field: fields
+/// stored as JSON are wrapped within `Json`, while encrypted fields are bound as bytes (an
+/// `Option` stays the outermost type, so `None` is still bound as `NULL`).
+pub fn encoded_type(field: &Field) -> TokenStream2 {
+    let ty = &field.ty;
+    let (inner, optional) = match option_inner(ty) {
+        Some(inner) => (inner, true),
+        None => (ty, false),
+    };
+
+    let encoded = if field.is_json() {
+        quote!(sqlx_extension::sqlx_extension_core::sqlx::types::Json<&'q #inner>)
+    } else if field.is_encrypted() {
+        quote!(Vec<u8>)
+    } else {
+        return quote!(#ty);
+    };
+
+    if optional {
+        quote!(Option<#encoded>)
+    } else {
+        encoded
+    }
+}
+
+/// This function returns the expression which is bound to the arguments for the given value
+/// (a reference to the value of the field), matching `encoded_type`.
+pub fn encoded_value(field: &Field, value: TokenStream2) -> TokenStream2 {
+    let wrap = if field.is_json() {
+        quote!(sqlx_extension::sqlx_extension_core::sqlx::types::Json)
+    } else if field.is_encrypted() {
+        quote!(sqlx_extension::sqlx_extension_core::cipher::encrypt_field)
+    } else {
+        return value;
+    };
+
+    match option_inner(&field.ty) {
+        Some(_) => quote!((#value).as_ref().map(#wrap)),
+        None => quote!(#wrap(#value)),
+    }
+}
+
+/// This function returns the type the column of the given field is decoded as.
+pub fn decoded_type(field: &Field) -> TokenStream2 {
+    let ty = &field.ty;
+    let (inner, optional) = match option_inner(ty) {
+        Some(inner) => (inner, true),
+        None => (ty, false),
+    };
+
+    let decoded = if field.is_json() {
+        quote!(sqlx_extension::sqlx_extension_core::sqlx::types::Json<#inner>)
+    } else if field.is_encrypted() {
+        quote!(Vec<u8>)
+    } else {
+        return quote!(#ty);
+    };
+
+    if optional {
+        quote!(Option<#decoded>)
+    } else {
+        decoded
+    }
+}
+
+/// This function converts the given decoded value (see `decoded_type`) into the type of the
+/// field, i.e. unwraps the `Json` of fields stored as JSON and decrypts encrypted fields.
+pub fn decoded_value(field: &Field, value: TokenStream2) -> TokenStream2 {
+    let optional = option_inner(&field.ty).is_some();
+
+    if field.is_json() {
+        if optional {
+            quote!(#value.map(|json| json.0))
+        } else {
+            quote!(#value.0)
+        }
+    } else if field.is_encrypted() {
+        let name = field.name();
+        let decrypt = quote!(sqlx_extension::sqlx_extension_core::cipher::decrypt_column);
+        if optional {
+            quote!(#value.map(|sealed| #decrypt(#name, sealed)).transpose()?)
+        } else {
+            quote!(#decrypt(#name, #value)?)
+        }
+    } else {
+        value
+    }
+}
+
+/// This function returns the bound required to encrypt and decrypt the given field (if it
+/// is encrypted at all).
+pub fn encryptable_bound(field: &Field) -> Option<TokenStream2> {
+    if !field.is_encrypted() {
+        return None;
+    }
+
+    let inner = option_inner(&field.ty).unwrap_or(&field.ty);
+    Some(quote!(#inner: sqlx_extension::sqlx_extension_core::cipher::Encryptable))
+}
+
+/// This function creates the override of `SqlDescribe::has_encrypted_fields` in case the
+/// given fields contain encrypted (or flattened) fields, which are encrypted as well in case
+/// their struct has encrypted fields.
+pub fn create_has_encrypted_fields(fields: &[&Field]) -> Option<TokenStream2> {
+    let encrypted = fields.iter().any(|f| f.is_encrypted());
+    let flattened = fields
+        .iter()
+        .filter(|f| f.flatten_prefix().is_some())
+        .map(|f| {
+            let ty = &f.ty;
+            quote!(<#ty as sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe>::has_encrypted_fields())
+        })
+        .collect::<Vec<_>>();
+    if !encrypted && flattened.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        /// Whether the entity has encrypted fields.
+        fn has_encrypted_fields() -> bool {
+            #encrypted #(|| #flattened)*
+        }
+    })
+}
+
+/// This function returns the type wrapped by an `Option` (if the given type is one).
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => arguments.args.iter().find_map(|a| match a {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
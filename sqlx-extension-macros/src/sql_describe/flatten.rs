@@ -0,0 +1,227 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::{dialect::Dialect, models::field::Field, query_builder::QueryColumn};
+use syn::Ident;
+
+use super::encoding::{decoded_type, decoded_value, encryptable_bound};
+
+/// This function creates the implementation of `FlattenColumns`, which allows the struct to
+/// be embedded into other entities.
+pub fn create_flatten_columns_impl(struct_name: &Ident, all_fields: &[&Field]) -> TokenStream2 {
+    let columns = create_columns(all_fields, false);
+
+    quote! {
+        impl sqlx_extension::sqlx_extension_core::traits::flatten_columns::FlattenColumns for #struct_name {
+            fn flatten_columns(prefix: &str) -> Vec<sqlx_extension::sqlx_extension_core::statements::StatementColumn> {
+                let mut columns = Vec::new();
+                #(#columns)*
+                columns
+            }
+        }
+    }
+}
+
+/// This function creates the implementation of `FromPrefixedRow`, which decodes every field
+/// from its (prefixed) column. In case it is requested (which is required for flattened,
+/// encrypted or fields stored as JSON, as the derive of sqlx can't decode them), `FromRow` is
+/// implemented as well.
+pub fn create_from_row_impls(
+    struct_name: &Ident,
+    all_fields: &[&Field],
+    implements_from_row: bool,
+) -> TokenStream2 {
+    let idents = all_fields.iter().map(|f| &f.identifier);
+    let bounds = all_fields.iter().map(|f| {
+        let ty = &f.ty;
+        if f.flatten_prefix().is_some() {
+            quote! {
+                #ty: sqlx_extension::sqlx_extension_core::traits::flatten_columns::FromPrefixedRow<'r, R>
+            }
+        } else {
+            let decoded = decoded_type(f);
+            let encryptable = encryptable_bound(f).map(|bound| quote!(#bound,));
+            quote! {
+                #encryptable
+                #decoded: sqlx_extension::sqlx_extension_core::sqlx::Decode<'r, R::Database>
+                    + sqlx_extension::sqlx_extension_core::sqlx::Type<R::Database>
+            }
+        }
+    });
+    let values = all_fields.iter().map(|f| {
+        let ty = &f.ty;
+        match f.flatten_prefix() {
+            Some(field_prefix) => quote! {
+                <#ty as sqlx_extension::sqlx_extension_core::traits::flatten_columns::FromPrefixedRow<'r, R>>::from_prefixed_row(
+                    row,
+                    &format!("{}{}", prefix, #field_prefix),
+                )?
+            },
+            None => {
+                let name = f.name();
+                let decoded = decoded_type(f);
+                let value = quote! {
+                    sqlx_extension::sqlx_extension_core::sqlx::Row::try_get::<#decoded, _>(
+                        row,
+                        &*format!("{}{}", prefix, #name),
+                    )?
+                };
+                decoded_value(f, value)
+            }
+        }
+    });
+
+    let from_row = if implements_from_row {
+        quote! {
+            impl<'r, R> sqlx_extension::sqlx_extension_core::sqlx::FromRow<'r, R> for #struct_name
+            where
+                R: sqlx_extension::sqlx_extension_core::sqlx::Row,
+                Self: sqlx_extension::sqlx_extension_core::traits::flatten_columns::FromPrefixedRow<'r, R>,
+            {
+                fn from_row(row: &'r R) -> sqlx_extension::sqlx_extension_core::sqlx::Result<Self> {
+                    <Self as sqlx_extension::sqlx_extension_core::traits::flatten_columns::FromPrefixedRow<'r, R>>::from_prefixed_row(row, "")
+                }
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    quote! {
+        impl<'r, R> sqlx_extension::sqlx_extension_core::traits::flatten_columns::FromPrefixedRow<'r, R> for #struct_name
+        where
+            R: sqlx_extension::sqlx_extension_core::sqlx::Row,
+            for<'a> &'a str: sqlx_extension::sqlx_extension_core::sqlx::ColumnIndex<R>,
+            #(#bounds,)*
+        {
+            fn from_prefixed_row(row: &'r R, prefix: &str) -> sqlx_extension::sqlx_extension_core::sqlx::Result<Self> {
+                Ok(Self {
+                    #(#idents: #values,)*
+                })
+            }
+        }
+
+        #from_row
+    }
+}
+
+/// This function creates the implementation of `RenderStatements`, which renders all
+/// statements at runtime (e.g. against the schema of a tenant). Entities with flattened fields
+/// render their statements using it as well, as the columns of the flattened fields aren't
+/// known at compile time.
+pub fn create_render_statements_impl(
+    struct_name: &Ident,
+    all_fields: &[&Field],
+    table_name: &str,
+    schema_name: Option<&str>,
+    dialect: Dialect,
+) -> TokenStream2 {
+    let columns = create_columns(all_fields, true);
+    let builder = create_builder(table_name, schema_name, dialect);
+
+    quote! {
+        impl sqlx_extension::sqlx_extension_core::traits::render_statements::RenderStatements for #struct_name {
+            fn render_statements(
+                schema_name: Option<&str>,
+            ) -> sqlx_extension::sqlx_extension_core::statements::Statements {
+                let prefix = "";
+                let mut columns = Vec::new();
+                #(#columns)*
+
+                let builder = #builder;
+
+                let columns = columns.iter().collect::<Vec<_>>();
+                sqlx_extension::sqlx_extension_core::statements::Statements::render(builder, &columns)
+            }
+        }
+    }
+}
+
+/// This function creates an expression setting up the query builder for the given table at
+/// runtime, qualified by `schema_name` (falling back to the given schema) and restricted to
+/// the tenant within `columns` (if any).
+pub fn create_builder(
+    table_name: &str,
+    schema_name: Option<&str>,
+    dialect: Dialect,
+) -> TokenStream2 {
+    let schema_name = match schema_name {
+        Some(schema_name) => quote!(schema_name.or(Some(#schema_name))),
+        None => quote!(schema_name),
+    };
+    let dialect = create_dialect(dialect);
+
+    quote! {
+        {
+            let builder = sqlx_extension::sqlx_extension_core::query_builder::QueryBuilder::new()
+                .table_name(#table_name)
+                .dialect(#dialect);
+            let builder = match #schema_name {
+                Some(schema_name) => builder.schema_name(schema_name),
+                None => builder,
+            };
+            match columns.iter().find(|c| c.tenant) {
+                Some(tenant) => builder.tenant_field(tenant),
+                None => builder,
+            }
+        }
+    }
+}
+
+/// This function creates an expression evaluating to the given dialect.
+pub fn create_dialect(dialect: Dialect) -> TokenStream2 {
+    let variant = match dialect {
+        Dialect::Postgres => quote!(Postgres),
+        Dialect::MySql => quote!(MySql),
+        Dialect::Sqlite => quote!(Sqlite),
+    };
+    quote!(sqlx_extension::sqlx_extension_core::dialect::Dialect::#variant)
+}
+
+/// This function creates a statement adding the column(s) of each field to `columns`,
+/// prefixed by `prefix`. The primary key, tenant and generated flags of the fields are only
+/// retained if requested.
+pub fn create_columns(all_fields: &[&Field], retain_keys: bool) -> Vec<TokenStream2> {
+    all_fields
+        .iter()
+        .map(|f| match f.flatten_prefix() {
+            Some(field_prefix) => {
+                let ty = &f.ty;
+                quote! {
+                    columns.extend(
+                        <#ty as sqlx_extension::sqlx_extension_core::traits::flatten_columns::FlattenColumns>::flatten_columns(
+                            &format!("{}{}", prefix, #field_prefix)
+                        )
+                    );
+                }
+            }
+            None => {
+                let name = f.name();
+                let primary_key = retain_keys && f.is_primary_key();
+                let tenant = retain_keys && f.is_tenant();
+                let generated = retain_keys && f.is_generated();
+                let sql_type = optional_string(f.sql_type());
+                let read_expr = optional_string(f.read_expr());
+                let write_expr = optional_string(f.write_expr());
+                quote! {
+                    columns.push(sqlx_extension::sqlx_extension_core::statements::StatementColumn {
+                        name: format!("{}{}", prefix, #name),
+                        primary_key: #primary_key,
+                        tenant: #tenant,
+                        generated: #generated,
+                        sql_type: #sql_type,
+                        read_expr: #read_expr,
+                        write_expr: #write_expr,
+                    });
+                }
+            }
+        })
+        .collect()
+}
+
+/// This function converts the given optional string into the tokens of an `Option<String>`.
+fn optional_string(value: Option<&str>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(Some(String::from(#value))),
+        None => quote!(None),
+    }
+}
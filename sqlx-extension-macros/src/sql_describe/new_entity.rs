@@ -0,0 +1,59 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use sqlx_extension_core::{models::field::Field, query_builder::QueryColumn};
+use syn::{Ident, Visibility};
+
+use super::bind::{create_bind_bound, create_bind_statement};
+
+/// This function creates the insert DTO of the given struct (e.g. `NewUser`), which contains
+/// every field neither annotated with `p_key` nor `generated`, i.e. exactly the fields bound
+/// by `insert_without_pk`, alongside the implementations of `NewEntity` and `BindNewEntity`.
+/// The DTO is only generated for structs annotated with `#[describe(new)]`.
+pub fn create_new_entity(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    fields: &[(&Visibility, &Field)],
+) -> TokenStream2 {
+    let new_name = format_ident!("New{}", struct_name);
+    let (field_visibilities, fields): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .filter(|(_, f)| !f.is_primary_key() && !f.is_generated())
+        .copied()
+        .unzip();
+    let idents = fields.iter().map(|f| &f.identifier);
+    let types = fields.iter().map(|f| &f.ty);
+
+    let bounds = fields.iter().map(|f| create_bind_bound(f));
+    let bind_statements = fields.iter().map(|f| {
+        let ident = &f.identifier;
+        create_bind_statement(f, quote!(&self.#ident))
+    });
+
+    let doc = format!(
+        "The fields required to insert a `{}`, generated by the `Describe` derive macro: \
+         `NewEntity::insert` returns the inserted entity.",
+        struct_name
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone)]
+        #visibility struct #new_name {
+            #(#field_visibilities #idents: #types,)*
+        }
+
+        impl sqlx_extension::sqlx_extension_core::traits::new_entity::NewEntity for #new_name {
+            type Entity = #struct_name;
+        }
+
+        impl<'q, DB> sqlx_extension::sqlx_extension_core::traits::new_entity::BindNewEntity<'q, DB> for #new_name
+        where
+            DB: sqlx_extension::sqlx_extension_core::sqlx::Database,
+            #(#bounds,)*
+        {
+            fn bind_new(&'q self, arguments: &mut <DB as sqlx_extension::sqlx_extension_core::sqlx::database::HasArguments<'q>>::Arguments) {
+                #(#bind_statements)*
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
+use sqlx_extension_core::statements::Statements;
 use syn::Ident;
 
 /// This function creates a query with a given name.
@@ -10,3 +11,39 @@ pub fn create_query_fn(fn_name: &Ident, sql: &str) -> TokenStream2 {
         }
     }
 }
+
+/// This function creates all queries from the statements rendered at compile time.
+pub fn create_query_fns(statements: &Statements) -> Vec<TokenStream2> {
+    statements
+        .named()
+        .iter()
+        .copied()
+        .chain(std::iter::once((
+            "select_list",
+            &statements.select_list[..],
+        )))
+        .map(|(name, sql)| create_query_fn(&Ident::new(name, Span::call_site()), sql))
+        .collect()
+}
+
+/// This function creates all queries, which return the statements rendered at runtime by
+/// the implementation of `RenderStatements` on first use. The statements are held by the
+/// static `STATEMENTS`, which has to be declared alongside the queries.
+pub fn create_runtime_query_fns(struct_name: &Ident) -> Vec<TokenStream2> {
+    Statements::default()
+        .named()
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(std::iter::once("select_list"))
+        .map(|name| {
+            let fn_name = Ident::new(name, Span::call_site());
+            quote! {
+                fn #fn_name() -> &'static str {
+                    &STATEMENTS.get_or_render(|| {
+                        <#struct_name as sqlx_extension::sqlx_extension_core::traits::render_statements::RenderStatements>::render_statements(None)
+                    }).#fn_name
+                }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,28 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use sqlx_extension_core::models::field::Field;
+use syn::Ident;
+
+/// This function creates the implementation of `TenantScoped` in case the struct has a tenant
+/// field, else the struct is marked as `Unscoped`.
+pub fn create_tenant_impl(struct_name: &Ident, tenant_field: Option<&Field>) -> TokenStream2 {
+    match tenant_field {
+        Some(field) => {
+            let ident = &field.identifier;
+            let ty = &field.ty;
+
+            quote! {
+                impl sqlx_extension::sqlx_extension_core::traits::tenant_scoped::TenantScoped for #struct_name {
+                    type Tenant = #ty;
+
+                    fn tenant(&self) -> &Self::Tenant {
+                        &self.#ident
+                    }
+                }
+            }
+        }
+        None => quote! {
+            impl sqlx_extension::sqlx_extension_core::traits::tenant_scoped::Unscoped for #struct_name {}
+        },
+    }
+}
@@ -1,29 +1,43 @@
-use std::convert::TryFrom;
-
 use proc_macro::TokenStream;
 use proc_macro2::{Group, Literal};
+use sqlx_extension_core::dialect::Dialect;
 use sqlx_extension_core::models::{
-    attribute::{Attribute as CustomAttribute, RawAttribute},
+    attribute::{Attribute as CustomAttribute, DescribeOption, RawAttribute},
     field::Field as CustomField,
+    rename_rule::RenameRule,
 };
 use syn::parse;
-use syn::{Attribute, Field, Ident, Path};
+use syn::{Attribute, Field, Ident, Path, Visibility};
 
 /// This function converts a SYN-field to a more usable
-/// field from the common crate.
-pub(crate) fn parse_field(field: Field) -> Option<CustomField> {
-    let Field { attrs, ident, .. } = field;
+/// field from the common crate, applying the rename rule of the struct (if any).
+pub(crate) fn parse_field(
+    field: Field,
+    rename_rule: Option<RenameRule>,
+) -> syn::Result<Option<CustomField>> {
+    let Field {
+        attrs, ident, ty, ..
+    } = field;
 
     // parse identifier
-    let identifier = ident?;
+    let identifier = match ident {
+        Some(identifier) => identifier,
+        None => return Ok(None),
+    };
 
     // parse attributes
-    let attributes = parse_attributes(attrs).collect();
+    let attributes = parse_attributes(attrs)?;
 
-    Some(CustomField {
+    let mut field = CustomField {
         identifier,
+        ty,
         attributes,
-    })
+    };
+    if let Some(rule) = rename_rule {
+        field.apply_rename_rule(rule);
+    }
+
+    Ok(Some(field))
 }
 
 /// This function converts a SYN-attribute to a more usable
@@ -35,16 +49,22 @@ pub(crate) fn parse_raw_attribute(attr: Attribute) -> Option<RawAttribute> {
     let input = TokenStream::from(attr.tokens);
     let group = parse::<Group>(input).ok();
 
-    let value = if let Some(group) = group {
-        // convert tokens within group to literal
+    let value = group.as_ref().map(|group| {
+        // convert tokens within group to literal; in case the group doesn't contain a
+        // literal (e.g. a type path), the raw tokens are used
         let input = TokenStream::from(group.stream());
-        let value = parse::<Literal>(input).ok()?;
-        Some(value.to_string())
-    } else {
-        None
-    };
+        match parse::<Literal>(input) {
+            Ok(value) => value.to_string(),
+            Err(_) => group.stream().to_string(),
+        }
+    });
+    let tokens = group.map(|group| group.stream());
 
-    Some(RawAttribute { name, value })
+    Some(RawAttribute {
+        name,
+        value,
+        tokens,
+    })
 }
 
 /// This function attempts to retrieve the underlying identifier
@@ -53,13 +73,61 @@ pub(crate) fn ident_from_path(path: Path) -> Option<Ident> {
     path.segments.into_iter().map(|s| s.ident).next()
 }
 
-/// This function parses all the given attributes.
+/// This function parses all the given attributes, skipping the ones of other crates. In
+/// case any attribute is invalid, an error spanned on the attribute is returned.
 pub(crate) fn parse_attributes(
     attrs: impl IntoIterator<Item = Attribute>,
-) -> impl Iterator<Item = CustomAttribute> {
+) -> syn::Result<Vec<CustomAttribute>> {
     attrs
         .into_iter()
         .filter_map(parse_raw_attribute)
-        .map(CustomAttribute::try_from)
-        .filter_map(Result::ok)
+        .filter_map(|a| CustomAttribute::parse(a).transpose())
+        .collect()
+}
+
+/// This function parses all the given named fields (alongside their visibility), applying
+/// the rename rule of the struct (if any).
+pub(crate) fn parse_fields(
+    fields: impl IntoIterator<Item = Field>,
+    rename_rule: Option<RenameRule>,
+) -> syn::Result<Vec<(Visibility, CustomField)>> {
+    fields
+        .into_iter()
+        .filter_map(|f| {
+            let visibility = f.vis.clone();
+            parse_field(f, rename_rule)
+                .map(|f| f.map(|f| (visibility, f)))
+                .transpose()
+        })
+        .collect()
+}
+
+/// This function parses the rename rule from the `describe` attribute of a struct (if any).
+pub(crate) fn parse_rename_rule<'a>(
+    attrs: impl IntoIterator<Item = &'a CustomAttribute> + 'a,
+) -> Option<RenameRule> {
+    attrs.into_iter().find_map(|a| match a {
+        CustomAttribute::Describe { options } => options.iter().find_map(|o| match o {
+            DescribeOption::RenameAll(rule) => Some(*rule),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+/// This function parses the dialect from the `describe` attribute of a struct, defaulting
+/// to Postgres.
+pub(crate) fn parse_dialect<'a>(
+    attrs: impl IntoIterator<Item = &'a CustomAttribute> + 'a,
+) -> Dialect {
+    attrs
+        .into_iter()
+        .find_map(|a| match a {
+            CustomAttribute::Describe { options } => options.iter().find_map(|o| match o {
+                DescribeOption::Dialect(dialect) => Some(*dialect),
+                _ => None,
+            }),
+            _ => None,
+        })
+        .unwrap_or_default()
 }
@@ -0,0 +1,5 @@
+//! This crate contains the tests of the derive macros, which are compiled against the
+//! `sqlx-extension` crate just like the code of its users. Every struct within the tests has
+//! to compile, so the tests double as compile tests of the generated code. The crate
+//! deliberately doesn't depend on `sqlx` directly, as the generated code must only refer to
+//! the one re-exported by `sqlx-extension-core`.
@@ -0,0 +1,100 @@
+use sqlx_extension::sqlx_extension_core::filter::Filter;
+use sqlx_extension::sqlx_extension_macros::Describe;
+
+#[derive(Debug, Describe)]
+#[table("products")]
+struct Product {
+    id: i32,
+    name: String,
+    price: i64,
+    #[sql_type("category_t")]
+    category: String,
+    discontinued_at: Option<i64>,
+}
+
+fn product(price: i64, discontinued_at: Option<i64>) -> Product {
+    Product {
+        id: 1,
+        name: "chair".into(),
+        price,
+        category: "furniture".into(),
+        discontinued_at,
+    }
+}
+
+#[test]
+fn compiles_comparisons() {
+    let filter = Product::PRICE.ge(10).and(Product::NAME.ne("table"));
+    assert_eq!(filter.to_sql(1), r#"("price" >= $1) AND ("name" <> $2)"#);
+    assert_eq!(filter.to_sql(3), r#"("price" >= $3) AND ("name" <> $4)"#);
+    assert_eq!(filter.placeholder_count(), 2);
+}
+
+#[test]
+fn compiles_checks_without_placeholders() {
+    let filter = Product::DISCONTINUED_AT.is_null().or(Product::PRICE.lt(5));
+    assert_eq!(
+        filter.to_sql(1),
+        r#"("discontinued_at" IS NULL) OR ("price" < $1)"#
+    );
+    assert_eq!(filter.placeholder_count(), 1);
+}
+
+#[test]
+fn casts_values_to_the_sql_type() {
+    let filter = Product::CATEGORY.eq("furniture");
+    assert_eq!(filter.to_sql(1), r#""category" = $1::category_t"#);
+}
+
+#[test]
+fn negates_filters() {
+    let filter = !Product::PRICE.gt(100);
+    assert_eq!(filter.to_sql(1), r#"NOT ("price" > $1)"#);
+    assert_eq!(filter.placeholder_count(), 1);
+
+    let filter = !!Product::PRICE.gt(100);
+    assert_eq!(filter.to_sql(1), r#""price" > $1"#);
+}
+
+#[test]
+fn detects_unrestricted_filters() {
+    assert!(Filter::<Product>::all().is_all());
+    assert!((!!Filter::<Product>::all()).is_all());
+    assert!(Product::PRICE.gt(1).or(Filter::all()).is_all());
+    assert!(!Product::PRICE.gt(1).and(Filter::all()).is_all());
+    assert!(!(!Filter::<Product>::all()).is_all());
+
+    assert_eq!(Filter::<Product>::all().where_clause(1), "");
+    assert_eq!(Product::ID.eq(1).where_clause(2), r#"WHERE "id" = $2"#);
+}
+
+#[test]
+fn evaluates_filters_in_memory() {
+    let filter = Product::PRICE
+        .ge(10)
+        .and(Product::DISCONTINUED_AT.is_null());
+    assert!(filter.matches(&product(10, None)));
+    assert!(!filter.matches(&product(9, None)));
+    assert!(!filter.matches(&product(10, Some(1))));
+    assert!((!filter).matches(&product(9, None)));
+}
+
+#[test]
+fn evaluates_comparisons_with_null_as_unknown() {
+    let filter = Product::DISCONTINUED_AT.eq(None);
+    assert!(!filter.matches(&product(10, None)));
+    assert!(!filter.matches(&product(10, Some(1))));
+
+    let filter = !Product::DISCONTINUED_AT.eq(1);
+    assert!(!filter.matches(&product(10, None)));
+    assert!(filter.matches(&product(10, Some(2))));
+
+    let filter = Product::DISCONTINUED_AT.eq(1).or(Product::PRICE.ge(10));
+    assert!(filter.matches(&product(10, None)));
+    assert!(!filter.matches(&product(9, None)));
+    assert!(!(!filter).matches(&product(9, None)));
+
+    let filter = Product::DISCONTINUED_AT.eq(1).and(Product::PRICE.ge(10));
+    assert!(!filter.matches(&product(9, None)));
+    assert!((!filter).matches(&product(9, None)));
+}
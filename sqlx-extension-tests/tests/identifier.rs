@@ -0,0 +1,97 @@
+use sqlx_extension::sqlx_extension_core::repository::{InMemoryRepository, Repository};
+use sqlx_extension::sqlx_extension_core::sqlx::postgres::PgRow;
+use sqlx_extension::sqlx_extension_core::sqlx::FromRow;
+use sqlx_extension::sqlx_extension_core::traits::entity::Entity;
+use sqlx_extension::sqlx_extension_macros::{Describe, Entity};
+
+#[derive(Debug, Clone, PartialEq, Describe, Entity)]
+#[table("memberships")]
+struct Membership {
+    #[p_key]
+    user_id: i32,
+    #[p_key]
+    group_id: i32,
+    role: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Describe, Entity)]
+#[table("tags")]
+struct Tag {
+    #[p_key]
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AccountId(i64);
+
+impl From<(i64,)> for AccountId {
+    fn from((id,): (i64,)) -> Self {
+        Self(id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Describe, Entity)]
+#[table("accounts")]
+#[ident(AccountId)]
+struct Account {
+    #[p_key]
+    id: i64,
+    balance: i64,
+}
+
+/// This function only compiles in case the given type can be decoded from a row.
+fn assert_from_row<T: for<'r> FromRow<'r, PgRow>>() {}
+
+#[test]
+fn generates_key_structs_for_composite_keys() {
+    let membership = Membership {
+        user_id: 1,
+        group_id: 2,
+        role: "admin".into(),
+    };
+
+    let key = membership.identifier();
+    assert_eq!(
+        key,
+        MembershipKey {
+            user_id: 1,
+            group_id: 2
+        }
+    );
+    assert_eq!(key, MembershipKey::from((1, 2)));
+    assert_from_row::<MembershipKey>();
+}
+
+#[test]
+fn infers_the_identifier_of_a_single_key() {
+    let tag = Tag {
+        name: "rust".into(),
+    };
+    let identifier: (String,) = tag.identifier();
+    assert_eq!(identifier, ("rust".to_string(),));
+}
+
+#[test]
+fn converts_keys_into_custom_identifiers() {
+    let account = Account { id: 7, balance: 0 };
+    assert_eq!(account.identifier(), AccountId(7));
+}
+
+#[tokio::test]
+async fn looks_up_entities_by_their_key() {
+    let membership = Membership {
+        user_id: 1,
+        group_id: 2,
+        role: "admin".into(),
+    };
+    let repository = InMemoryRepository::with_entities(vec![membership.clone()]);
+
+    assert_eq!(
+        repository.find(&MembershipKey::from((1, 2))).await.unwrap(),
+        Some(membership)
+    );
+    assert_eq!(
+        repository.find(&MembershipKey::from((2, 1))).await.unwrap(),
+        None
+    );
+}
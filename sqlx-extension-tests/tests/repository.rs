@@ -0,0 +1,94 @@
+use sqlx_extension::sqlx_extension_core::filter::Filter;
+use sqlx_extension::sqlx_extension_core::repository::{InMemoryRepository, Repository};
+use sqlx_extension::sqlx_extension_core::sqlx;
+use sqlx_extension::sqlx_extension_macros::{Describe, Entity};
+
+#[derive(Debug, Clone, PartialEq, Describe, Entity)]
+#[table("tasks")]
+struct Task {
+    #[p_key]
+    id: i32,
+    title: String,
+    done: bool,
+}
+
+fn task(id: i32, title: &str, done: bool) -> Task {
+    Task {
+        id,
+        title: title.into(),
+        done,
+    }
+}
+
+#[tokio::test]
+async fn inserts_and_finds_entities() {
+    let repository = InMemoryRepository::new();
+    assert!(repository.is_empty());
+
+    repository
+        .insert(&task(1, "write tests", false))
+        .await
+        .unwrap();
+    assert_eq!(repository.len(), 1);
+    assert_eq!(
+        repository.find(&(1,)).await.unwrap(),
+        Some(task(1, "write tests", false))
+    );
+    assert_eq!(repository.find(&(2,)).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn rejects_duplicate_identifiers() {
+    let repository = InMemoryRepository::with_entities(vec![task(1, "write tests", false)]);
+
+    let error = repository
+        .insert(&task(1, "review tests", false))
+        .await
+        .unwrap_err();
+    match error {
+        sqlx::Error::Database(e) => assert_eq!(e.code().as_deref(), Some("23505")),
+        e => panic!("unexpected error: {}", e),
+    }
+    assert_eq!(repository.len(), 1);
+}
+
+#[tokio::test]
+async fn updates_and_deletes_entities() {
+    let repository = InMemoryRepository::with_entities(vec![task(1, "write tests", false)]);
+
+    assert!(repository
+        .update(&task(1, "write tests", true))
+        .await
+        .unwrap());
+    assert!(!repository
+        .update(&task(2, "review tests", true))
+        .await
+        .unwrap());
+    assert_eq!(
+        repository.find(&(1,)).await.unwrap(),
+        Some(task(1, "write tests", true))
+    );
+
+    assert!(repository.delete(&(1,)).await.unwrap());
+    assert!(!repository.delete(&(1,)).await.unwrap());
+    assert!(repository.is_empty());
+}
+
+#[tokio::test]
+async fn lists_matching_entities() {
+    let repository = InMemoryRepository::with_entities(vec![
+        task(1, "write tests", true),
+        task(2, "review tests", false),
+        task(3, "merge", false),
+    ]);
+
+    let mut open = repository.list(&Task::DONE.eq(false)).await.unwrap();
+    open.sort_by_key(|t| t.id);
+    assert_eq!(
+        open,
+        vec![task(2, "review tests", false), task(3, "merge", false)]
+    );
+
+    let all = repository.list(&Filter::all()).await.unwrap();
+    assert_eq!(all.len(), 3);
+}
@@ -0,0 +1,315 @@
+use sqlx_extension::sqlx_extension_core::catalog::{catalog, dump_catalog};
+use sqlx_extension::sqlx_extension_core::tenant::TenantSchema;
+use sqlx_extension::sqlx_extension_core::traits::changeset::Changeset;
+use sqlx_extension::sqlx_extension_core::traits::sql_describe::SqlDescribe;
+use sqlx_extension::sqlx_extension_macros::Describe;
+
+#[derive(Debug, Describe)]
+#[describe(rename_all = "camelCase")]
+struct LegacyRecord {
+    #[p_key]
+    record_id: i32,
+    display_name: String,
+    #[rename("kind")]
+    record_kind: String,
+}
+
+#[derive(Debug, Describe)]
+struct AuditLog {
+    message: String,
+}
+
+#[derive(Debug, Describe)]
+#[describe(no_catalog)]
+struct ScratchRow {
+    value: String,
+}
+
+#[derive(Debug, Describe)]
+#[schema("app")]
+#[table("users")]
+#[describe(changeset)]
+struct User {
+    #[p_key]
+    id: i64,
+    #[rename("e_mail")]
+    email: String,
+    #[sql_type("kind_t")]
+    kind: String,
+}
+
+#[derive(Debug, Describe)]
+#[describe(dialect = "mysql")]
+struct Setting {
+    #[p_key]
+    key: String,
+    value: String,
+}
+
+#[derive(Debug, Describe)]
+#[describe(dialect = "sqlite")]
+struct Bookmark {
+    #[p_key]
+    id: i64,
+    url: String,
+}
+
+#[derive(Debug, Describe)]
+struct Document {
+    #[p_key]
+    #[tenant]
+    tenant_id: i32,
+    #[p_key]
+    id: i32,
+    title: String,
+}
+
+#[derive(Debug, Describe)]
+struct Follow {
+    #[p_key]
+    follower_id: i32,
+    #[p_key]
+    followee_id: i32,
+}
+
+#[derive(Debug, Describe)]
+#[describe(from_row)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Describe)]
+#[describe(from_row)]
+struct Customer {
+    #[p_key]
+    id: i32,
+    #[flatten("home_")]
+    home: Address,
+}
+
+#[derive(Debug, Describe)]
+#[describe(new)]
+struct Invoice {
+    #[p_key]
+    #[generated]
+    id: i64,
+    number: String,
+    #[generated]
+    issued_at: i64,
+}
+
+#[derive(Debug, Describe)]
+struct Place {
+    #[p_key]
+    id: i32,
+    #[read_expr("ST_AsText({})")]
+    #[write_expr("ST_GeomFromText({})")]
+    location: String,
+}
+
+#[test]
+fn derives_the_table_name_from_the_struct() {
+    assert_eq!(AuditLog::table_name(), r#""audit_log""#);
+    assert_eq!(AuditLog::retrieve_all(), r#"SELECT * FROM "audit_log";"#);
+}
+
+#[test]
+fn applies_the_rename_rule() {
+    assert_eq!(LegacyRecord::table_name(), r#""legacyRecord""#);
+    assert_eq!(
+        LegacyRecord::insert(),
+        r#"INSERT INTO "legacyRecord" ("recordId", "displayName", "kind") VALUES ($1, $2, $3) ;"#
+    );
+    assert_eq!(
+        LegacyRecord::update_by_pk(),
+        r#"UPDATE "legacyRecord" SET "displayName" = $1, "kind" = $2 WHERE "recordId" = $3;"#
+    );
+}
+
+#[test]
+fn qualifies_the_table_by_its_schema() {
+    assert_eq!(User::table_name(), r#""app"."users""#);
+    assert_eq!(
+        User::retrieve_by_pk(),
+        r#"SELECT * FROM "app"."users" WHERE "id" = $1;"#
+    );
+    assert_eq!(
+        User::update_by_pk(),
+        r#"UPDATE "app"."users" SET "e_mail" = $1, "kind" = $2::kind_t WHERE "id" = $3;"#
+    );
+}
+
+#[test]
+fn casts_placeholders_to_the_sql_type() {
+    assert_eq!(
+        User::insert(),
+        r#"INSERT INTO "app"."users" ("id", "e_mail", "kind") VALUES ($1, $2, $3::kind_t) ;"#
+    );
+    assert_eq!(
+        User::insert_without_pk(),
+        r#"INSERT INTO "app"."users" ("e_mail", "kind") VALUES ($1, $2::kind_t) RETURNING "id", "e_mail", "kind";"#
+    );
+}
+
+#[test]
+fn renders_statements_per_dialect() {
+    assert_eq!(
+        Setting::insert(),
+        "INSERT INTO `setting` (`key`, `value`) VALUES (?, ?) ;"
+    );
+    assert_eq!(
+        Setting::update_by_pk(),
+        "UPDATE `setting` SET `value` = ? WHERE `key` = ?;"
+    );
+    assert_eq!(
+        Setting::delete_by_pk(),
+        "DELETE FROM `setting` WHERE `key` = ?;"
+    );
+}
+
+#[test]
+fn omits_statements_unsupported_by_the_dialect() {
+    // MySQL doesn't support `RETURNING`
+    assert_eq!(Setting::insert_without_pk(), "");
+    assert_eq!(Setting::insert_without_pk_return_pk(), "");
+    // SQLite doesn't support row locks
+    assert_eq!(
+        Bookmark::retrieve_by_pk(),
+        r#"SELECT * FROM "bookmark" WHERE "id" = ?1;"#
+    );
+    assert_eq!(Bookmark::retrieve_by_pk_for_update(), "");
+    assert_eq!(Bookmark::retrieve_by_pk_for_share(), "");
+}
+
+#[test]
+fn renders_statements_against_the_tenant_schema() {
+    let tenant = TenantSchema::new("tenant_1").unwrap();
+    assert_eq!(tenant.table_name::<User>(), r#""tenant_1"."users""#);
+
+    let statements = tenant.statements::<User>();
+    assert_eq!(
+        statements.retrieve_by_pk,
+        r#"SELECT * FROM "tenant_1"."users" WHERE "id" = $1;"#
+    );
+    assert_eq!(
+        statements.delete_by_pk,
+        r#"DELETE FROM "tenant_1"."users" WHERE "id" = $1;"#
+    );
+    assert!(TenantSchema::new("tenant\"; DROP TABLE x").is_err());
+}
+
+#[test]
+fn restricts_statements_to_the_tenant() {
+    assert_eq!(
+        Document::retrieve_all(),
+        r#"SELECT * FROM "document" WHERE "tenant_id" = $1;"#
+    );
+    assert_eq!(
+        Document::retrieve_by_pk(),
+        r#"SELECT * FROM "document" WHERE "id" = $2 AND "tenant_id" = $1;"#
+    );
+    assert_eq!(
+        Document::update_by_pk(),
+        r#"UPDATE "document" SET "title" = $2 WHERE "id" = $3 AND "tenant_id" = $1;"#
+    );
+    assert_eq!(
+        Document::delete_by_pk(),
+        r#"DELETE FROM "document" WHERE "id" = $2 AND "tenant_id" = $1;"#
+    );
+}
+
+#[test]
+fn flattens_nested_structs() {
+    assert_eq!(
+        Customer::insert(),
+        r#"INSERT INTO "customer" ("id", "home_street", "home_city") VALUES ($1, $2, $3) ;"#
+    );
+    assert_eq!(
+        Customer::insert_without_pk_return_pk(),
+        r#"INSERT INTO "customer" ("home_street", "home_city") VALUES ($1, $2) RETURNING "id";"#
+    );
+    assert_eq!(
+        Customer::update_by_pk(),
+        r#"UPDATE "customer" SET "home_street" = $1, "home_city" = $2 WHERE "id" = $3;"#
+    );
+}
+
+#[test]
+fn wraps_columns_in_expressions() {
+    assert_eq!(
+        Place::insert(),
+        r#"INSERT INTO "place" ("id", "location") VALUES ($1, ST_GeomFromText($2)) ;"#
+    );
+    assert_eq!(
+        Place::retrieve_by_pk(),
+        r#"SELECT "id", ST_AsText("location") AS "location" FROM "place" WHERE "id" = $1;"#
+    );
+    assert_eq!(
+        Place::update_by_pk(),
+        r#"UPDATE "place" SET "location" = ST_GeomFromText($1) WHERE "id" = $2;"#
+    );
+}
+
+#[test]
+fn renders_changesets() {
+    let changeset = UserChangeset::default();
+    assert_eq!(changeset.update_by_pk_returning(), None);
+    assert_eq!(changeset.update_without_where(), None);
+
+    let changeset = UserChangeset {
+        kind: Some("admin".into()),
+        ..Default::default()
+    };
+    assert_eq!(
+        changeset.update_by_pk_returning(),
+        Some(r#"UPDATE "app"."users" SET "kind" = $1::kind_t WHERE "id" = $2 RETURNING *;"#)
+    );
+    assert_eq!(
+        changeset.update_without_where(),
+        Some(r#"UPDATE "app"."users" SET "kind" = $1::kind_t"#)
+    );
+    assert_eq!(changeset.placeholder_count(), 1);
+}
+
+#[test]
+fn generates_insert_structs_without_generated_fields() {
+    let invoice = NewInvoice {
+        number: "2021-001".into(),
+    };
+    assert_eq!(invoice.number, "2021-001");
+    assert_eq!(
+        Invoice::insert_without_pk(),
+        r#"INSERT INTO "invoice" ("number") VALUES ($1) RETURNING "id", "number", "issued_at";"#
+    );
+    assert_eq!(
+        Invoice::update_by_pk(),
+        r#"UPDATE "invoice" SET "number" = $1 WHERE "id" = $2;"#
+    );
+}
+
+#[test]
+fn describes_structs_consisting_of_primary_keys() {
+    assert_eq!(
+        Follow::insert_without_pk(),
+        r#"INSERT INTO "follow" DEFAULT VALUES RETURNING "follower_id", "followee_id";"#
+    );
+    assert_eq!(
+        Follow::retrieve_by_pk(),
+        r#"SELECT * FROM "follow" WHERE "follower_id" = $1 AND "followee_id" = $2;"#
+    );
+    assert_eq!(Follow::update_by_pk(), "");
+}
+
+#[test]
+fn registers_structs_within_the_catalog_unless_opted_out() {
+    let entities = catalog()
+        .into_iter()
+        .map(|entry| entry.entity())
+        .collect::<Vec<_>>();
+    assert!(entities.contains(&"statements::AuditLog"));
+    assert!(!entities.contains(&"statements::ScratchRow"));
+    let dump = dump_catalog();
+    assert!(dump.contains(AuditLog::retrieve_all()));
+    assert!(!dump.contains(ScratchRow::retrieve_all()));
+}
@@ -0,0 +1,55 @@
+use sqlx_extension::sqlx_extension_core::tracked::Tracked;
+use sqlx_extension::sqlx_extension_core::traits::changeset::Changeset;
+use sqlx_extension::sqlx_extension_macros::Describe;
+
+#[derive(Debug, Clone, Describe)]
+#[describe(changeset)]
+struct Account {
+    #[p_key]
+    id: i32,
+    name: String,
+    balance: i64,
+}
+
+fn account() -> Tracked<Account> {
+    Tracked::new(Account {
+        id: 1,
+        name: "Martin".into(),
+        balance: 100,
+    })
+}
+
+#[test]
+fn starts_without_changes() {
+    let account = account();
+    assert!(!account.is_dirty());
+    assert!(account.dirty_fields().is_empty());
+    assert_eq!(account.changeset().update_by_pk_returning(), None);
+}
+
+#[test]
+fn tracks_changed_fields() {
+    let mut account = account();
+    account.set(AccountChangeset::NAME, "Martina".into());
+    account.modify(AccountChangeset::BALANCE, |balance| *balance += 10);
+
+    assert!(account.is_dirty());
+    assert_eq!(account.dirty_fields(), vec!["name", "balance"]);
+    assert_eq!(account.name, "Martina");
+    assert_eq!(account.balance, 110);
+}
+
+#[test]
+fn updates_only_changed_fields() {
+    let mut account = account();
+    account.modify(AccountChangeset::BALANCE, |balance| *balance -= 30);
+
+    let changeset = account.changeset();
+    assert_eq!(changeset.name, None);
+    assert_eq!(changeset.balance, Some(70));
+    assert_eq!(
+        changeset.update_by_pk_returning(),
+        Some(r#"UPDATE "account" SET "balance" = $1 WHERE "id" = $2 RETURNING *;"#)
+    );
+    assert_eq!(changeset.placeholder_count(), 1);
+}